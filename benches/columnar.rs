@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hash_table::{ColumnarHashTable, HashTable};
+
+const ENTRIES: u64 = 100_000;
+
+// big enough that dragging values through cache dominates a probe
+type LargeValue = [u8; 512];
+
+fn contains_large_values(c: &mut Criterion) {
+    let mut hash_table = HashTable::new();
+    let mut columnar = ColumnarHashTable::new();
+    for k in 0..ENTRIES {
+        hash_table.insert(k, [k as u8; 512] as LargeValue);
+        columnar.insert(k, [k as u8; 512] as LargeValue);
+    }
+
+    let mut group = c.benchmark_group("contains_key with 512 byte values");
+    // half hits and half misses
+    group.bench_function("HashTable", |b| {
+        b.iter(|| {
+            (ENTRIES / 2..ENTRIES * 3 / 2)
+                .filter(|k| hash_table.get(black_box(k)).is_some())
+                .count()
+        })
+    });
+    group.bench_function("ColumnarHashTable", |b| {
+        b.iter(|| {
+            (ENTRIES / 2..ENTRIES * 3 / 2)
+                .filter(|k| columnar.contains_key(black_box(k)))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn key_scan_large_values(c: &mut Criterion) {
+    let mut hash_table = HashTable::new();
+    let mut columnar = ColumnarHashTable::new();
+    for k in 0..ENTRIES {
+        hash_table.insert(k, [k as u8; 512] as LargeValue);
+        columnar.insert(k, [k as u8; 512] as LargeValue);
+    }
+
+    let mut group = c.benchmark_group("sum of keys with 512 byte values");
+    group.bench_function("HashTable", |b| {
+        b.iter(|| (&hash_table).into_iter().map(|(k, _)| *k).sum::<u64>())
+    });
+    group.bench_function("ColumnarHashTable", |b| {
+        b.iter(|| columnar.keys().sum::<u64>())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, contains_large_values, key_scan_large_values);
+criterion_main!(benches);
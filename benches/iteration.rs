@@ -0,0 +1,38 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hash_table::HashTable;
+
+fn iterate_sparse(c: &mut Criterion) {
+    // a table that grew big and then emptied out mostly looks like this
+    let mut hash_table = HashTable::with_capacity(1_000_000);
+    for k in 0..1_000u64 {
+        hash_table.insert(k * 1_000, k);
+    }
+
+    c.bench_function("iterate 1k entries over 1M buckets", |b| {
+        b.iter(|| {
+            black_box(&hash_table)
+                .into_iter()
+                .map(|(_, v)| *v)
+                .sum::<u64>()
+        })
+    });
+}
+
+fn iterate_dense(c: &mut Criterion) {
+    let mut hash_table = HashTable::new();
+    for k in 0..100_000u64 {
+        hash_table.insert(k, k);
+    }
+
+    c.bench_function("iterate 100k entries", |b| {
+        b.iter(|| {
+            black_box(&hash_table)
+                .into_iter()
+                .map(|(_, v)| *v)
+                .sum::<u64>()
+        })
+    });
+}
+
+criterion_group!(benches, iterate_sparse, iterate_dense);
+criterion_main!(benches);
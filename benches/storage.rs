@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hash_table::HashTable;
+
+const ENTRIES: u64 = 100_000;
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert 100k u64 keys");
+    group.bench_function("HashTable", |b| {
+        b.iter(|| {
+            let mut hash_table = HashTable::new();
+            for k in 0..ENTRIES {
+                hash_table.insert(black_box(k), k);
+            }
+            hash_table
+        })
+    });
+    // std's table as a reference point
+    group.bench_function("std HashMap", |b| {
+        b.iter(|| {
+            let mut map = HashMap::new();
+            for k in 0..ENTRIES {
+                map.insert(black_box(k), k);
+            }
+            map
+        })
+    });
+    group.finish();
+}
+
+fn get(c: &mut Criterion) {
+    let mut hash_table = HashTable::new();
+    let mut map = HashMap::new();
+    for k in 0..ENTRIES {
+        hash_table.insert(k, k);
+        map.insert(k, k);
+    }
+
+    let mut group = c.benchmark_group("get with half hits and half misses");
+    group.bench_function("HashTable", |b| {
+        b.iter(|| {
+            (ENTRIES / 2..ENTRIES * 3 / 2)
+                .filter(|k| hash_table.get(black_box(k)).is_some())
+                .count()
+        })
+    });
+    group.bench_function("std HashMap", |b| {
+        b.iter(|| {
+            (ENTRIES / 2..ENTRIES * 3 / 2)
+                .filter(|k| map.contains_key(black_box(k)))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn remove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove 100k u64 keys");
+    group.bench_function("HashTable", |b| {
+        b.iter_batched(
+            || {
+                let mut hash_table = HashTable::new();
+                for k in 0..ENTRIES {
+                    hash_table.insert(k, k);
+                }
+                hash_table
+            },
+            |mut hash_table| {
+                for k in 0..ENTRIES {
+                    hash_table.remove(black_box(&k));
+                }
+                hash_table
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, insert, get, remove);
+criterion_main!(benches);
@@ -0,0 +1,226 @@
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{DefaultSimpleHasher, Entry, HashTable, SimpleHasher};
+
+struct Accessed<V> {
+    v: V,
+    // nanoseconds between the table's epoch and the last access, atomic so reads can record through `&self`
+    last_accessed: AtomicU64,
+}
+
+/// A hash table that records when each entry was last accessed, for caches and for tracking down
+/// stale entries.
+///
+/// Inserting, `get`, `get_mut` and `touch` count as accesses, `peek` does not.
+///
+/// Entries can also be collected GC-style: `begin_mark` starts a cycle and `sweep_unaccessed` then
+/// removes everything that hasn't been accessed since.
+pub struct AccessTrackedHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, Accessed<V>, H>,
+    epoch: Instant,
+    // entries last accessed before this many nanoseconds past the epoch are swept
+    mark: u64,
+}
+
+impl<K, V> Default for AccessTrackedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        AccessTrackedHashTable {
+            inner: Default::default(),
+            epoch: Instant::now(),
+            mark: 0,
+        }
+    }
+}
+
+impl<K, V> AccessTrackedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> AccessTrackedHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> AccessTrackedHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> AccessTrackedHashTable<K, V, H> {
+        AccessTrackedHashTable {
+            inner: HashTable::with_hasher(hasher),
+            epoch: Instant::now(),
+            mark: 0,
+        }
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let now = self.now();
+        match self.inner.entry(k) {
+            Entry::Occupied(mut e) => {
+                let accessed = e.get_mut();
+                *accessed.last_accessed.get_mut() = now;
+                Some(std::mem::replace(&mut accessed.v, v))
+            }
+            Entry::Vacant(e) => {
+                e.insert_entry(Accessed {
+                    v,
+                    last_accessed: AtomicU64::new(now),
+                });
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let accessed = self.inner.get(k)?;
+        accessed.last_accessed.store(self.now(), Ordering::Relaxed);
+        Some(&accessed.v)
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let now = self.now();
+        let accessed = self.inner.get_mut(k)?;
+        *accessed.last_accessed.get_mut() = now;
+        Some(&mut accessed.v)
+    }
+
+    /// Looks up `k` without recording an access.
+    pub fn peek(&self, k: &K) -> Option<&V> {
+        self.inner.get(k).map(|accessed| &accessed.v)
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        self.inner.remove(k).map(|accessed| accessed.v)
+    }
+
+    /// Records an access to `k` without reading it. Returns whether it was present.
+    pub fn touch(&self, k: &K) -> bool {
+        match self.inner.get(k) {
+            Some(accessed) => {
+                accessed.last_accessed.store(self.now(), Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn last_accessed(&self, k: &K) -> Option<Instant> {
+        let accessed = self.inner.get(k)?;
+        Some(self.at(accessed.last_accessed.load(Ordering::Relaxed)))
+    }
+
+    /// Iterates the entries that haven't been accessed for longer than `idle`, without recording
+    /// accesses to them.
+    pub fn iter_idle(&self, idle: Duration) -> impl Iterator<Item = (&K, &V)> {
+        let now = self.now();
+        let idle = idle.as_nanos().min(u64::MAX as u128) as u64;
+        (&self.inner).into_iter().filter_map(move |(k, accessed)| {
+            let last_accessed = accessed.last_accessed.load(Ordering::Relaxed);
+            if now.saturating_sub(last_accessed) > idle {
+                Some((k, &accessed.v))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Starts a collection cycle: the next `sweep_unaccessed` removes every entry not accessed after this.
+    pub fn begin_mark(&mut self) {
+        self.mark = self.now();
+    }
+
+    /// Removes every entry that hasn't been accessed since the last `begin_mark`, returning how many
+    /// were removed. Without a mark nothing is removed.
+    pub fn sweep_unaccessed(&mut self) -> usize {
+        self.sweep_unaccessed_with(|_, _| {})
+    }
+
+    /// Like `sweep_unaccessed`, handing every removed entry to `evicted`.
+    pub fn sweep_unaccessed_with<F>(&mut self, mut evicted: F) -> usize
+    where
+        F: FnMut(K, V),
+    {
+        let mark = self.mark;
+        self.inner.extract_entries(
+            |_, accessed| *accessed.last_accessed.get_mut() < mark,
+            |k, accessed| evicted(k, accessed.v),
+        )
+    }
+
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_nanos().min(u64::MAX as u128) as u64
+    }
+
+    fn at(&self, nanos: u64) -> Instant {
+        self.epoch + Duration::from_nanos(nanos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::AccessTrackedHashTable;
+
+    #[test]
+    fn test_access_tracking() {
+        let before = Instant::now();
+        let mut hash_table = AccessTrackedHashTable::new();
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("theo", 0);
+        hash_table.insert("aviva", 26);
+        assert!(hash_table.last_accessed(&"gedalia").unwrap() >= before);
+        assert_eq!(hash_table.last_accessed(&"nowhereman"), None);
+
+        thread::sleep(Duration::from_millis(50));
+        let touched = Instant::now();
+        assert_eq!(hash_table.get(&"gedalia"), Some(&27));
+        assert!(hash_table.touch(&"theo"));
+        assert!(!hash_table.touch(&"nowhereman"));
+        // peeking doesn't count as an access
+        assert_eq!(hash_table.peek(&"aviva"), Some(&26));
+
+        assert!(hash_table.last_accessed(&"theo").unwrap() >= touched);
+        assert!(hash_table.last_accessed(&"aviva").unwrap() < touched);
+        let idle: Vec<_> = hash_table.iter_idle(Duration::from_millis(25)).collect();
+        assert_eq!(idle, vec![(&"aviva", &26)]);
+    }
+
+    #[test]
+    fn test_mark_and_sweep() {
+        let mut hash_table = AccessTrackedHashTable::new();
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("theo", 0);
+        hash_table.insert("aviva", 26);
+        // nothing is swept before the first mark
+        assert_eq!(hash_table.sweep_unaccessed(), 0);
+
+        thread::sleep(Duration::from_millis(1));
+        hash_table.begin_mark();
+        thread::sleep(Duration::from_millis(1));
+        hash_table.get(&"gedalia");
+        hash_table.insert("chani", 25);
+
+        let mut evicted = vec![];
+        assert_eq!(
+            hash_table.sweep_unaccessed_with(|k, v| evicted.push((k, v))),
+            2
+        );
+        evicted.sort();
+        assert_eq!(evicted, vec![("aviva", 26), ("theo", 0)]);
+        assert_eq!(hash_table.peek(&"gedalia"), Some(&27));
+        assert_eq!(hash_table.peek(&"chani"), Some(&25));
+        assert_eq!(hash_table.sweep_unaccessed(), 0);
+    }
+}
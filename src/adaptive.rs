@@ -0,0 +1,85 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// how many probes are watched before the load factor is reconsidered
+const WINDOW: u64 = 256;
+
+// the bands of average keys compared per probe the load factor is steered into, for hits and for
+// misses. uniform hashing at the default 0.75 load factor averages about 1.4 for hits, and misses walk
+// their whole chain, about 0.75 entries
+const TOO_SLOW_HITS: f64 = 1.5;
+const ROOM_TO_SPARE_HITS: f64 = 1.1;
+const TOO_SLOW_MISSES: f64 = 1.1;
+const ROOM_TO_SPARE_MISSES: f64 = 0.5;
+
+// how far a single adjustment moves the load factor
+const STEP: f64 = 1.1;
+
+/// Tracks how many keys probes compare against and nudges a table's load factor between two bounds:
+/// down, trading memory for shorter chains, while probes run long, and up while they stay short.
+///
+/// What counts as long depends on the hit rate. A miss walks its whole chain while a hit stops
+/// halfway on average, so the band probes are held to moves from the hits' towards the misses' as
+/// the share of misses grows. That keeps a miss-heavy workload from being steered to a lower load
+/// factor than a hit-heavy one with the same chains.
+pub(crate) struct AdaptiveLoadFactor {
+    min: f64,
+    max: f64,
+    // probes are recorded through `&self` by lookups, so these are atomics to keep tables `Sync`
+    probes: AtomicU64,
+    hits: AtomicU64,
+    compared: AtomicU64,
+}
+
+impl AdaptiveLoadFactor {
+    pub(crate) fn new(min: f64, max: f64) -> AdaptiveLoadFactor {
+        AdaptiveLoadFactor {
+            min,
+            max,
+            probes: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            compared: AtomicU64::new(0),
+        }
+    }
+
+    // the same bounds with nothing observed yet
+    pub(crate) fn fresh(&self) -> AdaptiveLoadFactor {
+        AdaptiveLoadFactor::new(self.min, self.max)
+    }
+
+    pub(crate) fn clamp(&self, load_factor: f64) -> f64 {
+        load_factor.clamp(self.min, self.max)
+    }
+
+    pub(crate) fn record(&self, probe_length: usize, hit: bool) {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        if hit {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        self.compared
+            .fetch_add(probe_length as u64, Ordering::Relaxed);
+    }
+
+    /// The load factor to use from now on, given the current one. Only changes once a full window of
+    /// probes has been observed, which also starts the next window.
+    pub(crate) fn adjust(&self, load_factor: f64) -> f64 {
+        let probes = self.probes.load(Ordering::Relaxed);
+        if probes < WINDOW {
+            return load_factor;
+        }
+        let compared = self.compared.swap(0, Ordering::Relaxed);
+        let hits = self.hits.swap(0, Ordering::Relaxed);
+        self.probes.store(0, Ordering::Relaxed);
+
+        let average = compared as f64 / probes as f64;
+        let hit_rate = hits as f64 / probes as f64;
+        let too_slow = hit_rate * TOO_SLOW_HITS + (1.0 - hit_rate) * TOO_SLOW_MISSES;
+        let room_to_spare = hit_rate * ROOM_TO_SPARE_HITS + (1.0 - hit_rate) * ROOM_TO_SPARE_MISSES;
+        if average > too_slow {
+            self.clamp(load_factor / STEP)
+        } else if average < room_to_spare {
+            self.clamp(load_factor * STEP)
+        } else {
+            load_factor
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use alloc::sync::Arc;
+use core::hash::Hash;
+
+use crate::{DefaultSimpleHasher, HashTable, SimpleHasher};
+
+/// A hash table that keeps each value behind an `Arc`, so `get_arc` can hand out a value that
+/// outlives the borrow of the table for the cost of a reference count increment. Useful when values
+/// are held across `.await`s or sent over channels while the table keeps changing.
+///
+/// Values stay immutable once inserted; replace them with `insert` to change them.
+pub struct ArcHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, Arc<V>, H>,
+}
+
+impl<K, V> Default for ArcHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        ArcHashTable {
+            inner: Default::default(),
+        }
+    }
+}
+
+impl<K, V> ArcHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> ArcHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> ArcHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> ArcHashTable<K, V, H> {
+        ArcHashTable {
+            inner: HashTable::with_hasher(hasher),
+        }
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<Arc<V>> {
+        self.inner.insert(k, Arc::new(v))
+    }
+
+    /// Inserts a value that is already shared, without allocating a new `Arc`.
+    pub fn insert_arc(&mut self, k: K, v: Arc<V>) -> Option<Arc<V>> {
+        self.inner.insert(k, v)
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k).map(|v| &**v)
+    }
+
+    /// Returns a new handle to the value, which stays valid however the table changes afterwards.
+    pub fn get_arc(&self, k: &K) -> Option<Arc<V>> {
+        self.inner.get(k).map(Arc::clone)
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<Arc<V>> {
+        self.inner.remove(k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::ArcHashTable;
+
+    #[test]
+    fn test_get_arc_outlives_the_borrow() {
+        let mut hash_table = ArcHashTable::new();
+        hash_table.insert("gedalia", vec![27; 1000]);
+
+        let ages = hash_table.get_arc(&"gedalia").unwrap();
+        let old = hash_table.insert("gedalia", vec![28]).unwrap();
+        assert!(Arc::ptr_eq(&ages, &old));
+        assert_eq!(ages.len(), 1000);
+        assert_eq!(hash_table.get(&"gedalia"), Some(&vec![28]));
+
+        let shared = Arc::new(vec![0]);
+        hash_table.insert_arc("theo", Arc::clone(&shared));
+        assert!(Arc::ptr_eq(&hash_table.get_arc(&"theo").unwrap(), &shared));
+        assert_eq!(hash_table.remove(&"theo"), Some(shared));
+    }
+}
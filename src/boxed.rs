@@ -0,0 +1,226 @@
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use core::mem;
+
+use crate::{DefaultSimpleHasher, HashTable, HashTableBuilder, SimpleHasher};
+
+// values up to two cache lines are cheap enough to keep in the table itself
+const DEFAULT_MAX_INLINE_SIZE: usize = 128;
+
+/// A hash table that keeps values bigger than a size threshold in their own allocations, while
+/// handing out plain `&V`s either way.
+///
+/// Tables store entries side by side, so huge values make every entry huge: probes step over more
+/// memory and each insert that reallocates copies all of it. Boxing keeps entries at the size of a
+/// key and a pointer, for the cost of an allocation per value and a pointer chase per read, which
+/// only pays off once values run to a few cache lines. So `V` is boxed only when it's bigger than
+/// `with_max_inline_size`'s threshold, 128 bytes by default, and otherwise stored inline like any
+/// other table's values. Every `V` is the same size, so the choice is made once, when the table is
+/// created.
+pub struct BoxedHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    storage: Storage<K, V, H>,
+}
+
+enum Storage<K, V, H>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    Inline(HashTable<K, V, H>),
+    Boxed(HashTable<K, Box<V>, H>),
+}
+
+impl<K, V> Default for BoxedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        BoxedHashTable::with_max_inline_size(DEFAULT_MAX_INLINE_SIZE)
+    }
+}
+
+impl<K, V> BoxedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> BoxedHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+
+    /// A table that boxes values when `V` is bigger than `max_inline_size` bytes.
+    pub fn with_max_inline_size(
+        max_inline_size: usize,
+    ) -> BoxedHashTable<K, V, DefaultSimpleHasher> {
+        HashTable::builder().build_boxed(max_inline_size)
+    }
+}
+
+impl<K, V, H> BoxedHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> BoxedHashTable<K, V, H> {
+        HashTable::builder()
+            .hasher(hasher)
+            .build_boxed(DEFAULT_MAX_INLINE_SIZE)
+    }
+
+    pub(crate) fn from_builder(
+        builder: HashTableBuilder<K, V, H>,
+        max_inline_size: usize,
+    ) -> BoxedHashTable<K, V, H> {
+        let storage = if mem::size_of::<V>() > max_inline_size {
+            Storage::Boxed(builder.values().build())
+        } else {
+            Storage::Inline(builder.build())
+        };
+        BoxedHashTable { storage }
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        match &mut self.storage {
+            Storage::Inline(inner) => inner.insert(k, v),
+            Storage::Boxed(inner) => inner.insert(k, Box::new(v)).map(|v| *v),
+        }
+    }
+
+    /// Inserts a value that is already boxed. Values the table boxes stay in their allocation, ones
+    /// it stores inline are moved out of it.
+    pub fn insert_boxed(&mut self, k: K, v: Box<V>) -> Option<Box<V>> {
+        match &mut self.storage {
+            Storage::Inline(inner) => inner.insert(k, *v).map(Box::new),
+            Storage::Boxed(inner) => inner.insert(k, v),
+        }
+    }
+
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        match &self.storage {
+            Storage::Inline(inner) => inner.get(k),
+            Storage::Boxed(inner) => inner.get(k).map(|v| &**v),
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        match &mut self.storage {
+            Storage::Inline(inner) => inner.get_mut(k),
+            Storage::Boxed(inner) => inner.get_mut(k).map(|v| &mut **v),
+        }
+    }
+
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        match &mut self.storage {
+            Storage::Inline(inner) => inner.remove(k),
+            Storage::Boxed(inner) => inner.remove(k).map(|v| *v),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(inner) => inner.len(),
+            Storage::Boxed(inner) => inner.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let (inline, boxed) = match &self.storage {
+            Storage::Inline(inner) => (Some(inner), None),
+            Storage::Boxed(inner) => (None, Some(inner)),
+        };
+        // only one of the two is there to iterate
+        let inline = inline.into_iter().flatten().map(|(k, v)| (k, v));
+        let boxed = boxed.into_iter().flatten().map(|(k, v)| (k, &**v));
+        inline.chain(boxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::string::{String, ToString};
+
+    use crate::{BoxedHashTable, HashTable};
+
+    use super::Storage;
+
+    #[test]
+    fn test_values_are_boxed() {
+        let mut hash_table = BoxedHashTable::new();
+        for k in 0..100u64 {
+            assert_eq!(hash_table.insert(k, [k as u8; 4096]), None);
+        }
+        assert!(matches!(hash_table.storage, Storage::Boxed(_)));
+        assert_eq!(hash_table.len(), 100);
+        assert_eq!(hash_table.insert(7, [0; 4096]), Some([7; 4096]));
+
+        hash_table.get_mut(&8).unwrap()[0] = 1;
+        assert_eq!(hash_table.get(&8).unwrap()[..2], [1, 8]);
+        assert_eq!(hash_table.remove(&9), Some([9; 4096]));
+        assert_eq!(hash_table.get(&9), None);
+
+        // values stay in the allocation they were inserted in
+        let boxed = Box::new([42; 4096]);
+        let pointer: *const [u8; 4096] = &*boxed;
+        hash_table.insert_boxed(42, boxed);
+        assert!(core::ptr::eq(hash_table.get(&42).unwrap(), pointer));
+
+        assert_eq!(hash_table.iter().count(), 99);
+        assert!(hash_table
+            .iter()
+            .all(|(k, v)| *k == 7 || *k == 8 || v[1] == *k as u8));
+    }
+
+    #[test]
+    fn test_small_values_are_stored_inline() {
+        let mut hash_table: BoxedHashTable<String, [u8; 16]> = BoxedHashTable::new();
+        assert!(matches!(hash_table.storage, Storage::Inline(_)));
+        for k in 0..100u8 {
+            assert_eq!(hash_table.insert(k.to_string(), [k; 16]), None);
+        }
+        assert_eq!(
+            hash_table.insert_boxed("7".to_string(), Box::new([0; 16])),
+            Some(Box::new([7; 16]))
+        );
+
+        // keys are looked up by any borrowed form, here `&str` for `String`
+        hash_table.get_mut("8").unwrap()[0] = 1;
+        assert_eq!(hash_table.get("8").unwrap()[..2], [1, 8]);
+        assert_eq!(hash_table.remove("9"), Some([9; 16]));
+        assert_eq!(hash_table.get("9"), None);
+        assert_eq!(hash_table.iter().count(), 99);
+        assert!(hash_table
+            .iter()
+            .all(|(k, v)| k == "7" || k == "8" || k.parse() == Ok(v[1])));
+
+        // the threshold decides, so even small values can be boxed
+        let hash_table: BoxedHashTable<u64, [u8; 16]> = BoxedHashTable::with_max_inline_size(8);
+        assert!(matches!(hash_table.storage, Storage::Boxed(_)));
+        let hash_table: BoxedHashTable<u64, [u8; 4096]> =
+            HashTable::builder().capacity(10).build_boxed(4096);
+        assert!(matches!(hash_table.storage, Storage::Inline(_)));
+    }
+}
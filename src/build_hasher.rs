@@ -0,0 +1,76 @@
+use core::hash::{BuildHasher, Hash};
+
+use crate::{HashTable, SimpleHasher};
+
+/// A `SimpleHasher` backed by any std `BuildHasher`, such as `RandomState` or the hashers from crates
+/// like ahash and fxhash. Every key is hashed by a fresh `Hasher` from the builder.
+#[derive(Clone, Debug, Default)]
+pub struct BuildHasherAdapter<S> {
+    build_hasher: S,
+}
+
+impl<S> BuildHasherAdapter<S> {
+    pub fn new(build_hasher: S) -> BuildHasherAdapter<S> {
+        BuildHasherAdapter { build_hasher }
+    }
+
+    pub fn build_hasher(&self) -> &S {
+        &self.build_hasher
+    }
+}
+
+impl<K, S> SimpleHasher<K> for BuildHasherAdapter<S>
+where
+    K: Hash + ?Sized,
+    S: BuildHasher,
+{
+    fn hash(&self, t: &K) -> u64 {
+        self.build_hasher.hash_one(t)
+    }
+}
+
+impl<K, V, S> HashTable<K, V, BuildHasherAdapter<S>>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    /// A table hashing its keys with a std `BuildHasher`.
+    pub fn with_build_hasher(build_hasher: S) -> HashTable<K, V, BuildHasherAdapter<S>> {
+        HashTable::with_hasher(BuildHasherAdapter::new(build_hasher))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::{DefaultHasher, RandomState};
+    use std::hash::BuildHasherDefault;
+
+    use crate::HashTable;
+
+    #[test]
+    fn test_with_build_hasher() {
+        let mut hash_table = HashTable::with_build_hasher(RandomState::new());
+        for k in 0..100 {
+            hash_table.insert(k.to_string(), k);
+        }
+        for k in 0..100 {
+            assert_eq!(hash_table.get(k.to_string().as_str()), Some(&k));
+        }
+
+        let mut hash_table =
+            HashTable::with_build_hasher(BuildHasherDefault::<DefaultHasher>::default());
+        hash_table.insert("gedalia", 27);
+        assert_eq!(hash_table.remove("gedalia"), Some(27));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_default_hasher_is_seeded_per_instance() {
+        use crate::{DefaultSimpleHasher, SimpleHasher};
+
+        let a = DefaultSimpleHasher::default();
+        let b = DefaultSimpleHasher::default();
+        assert_ne!(a.hash("gedalia"), b.hash("gedalia"));
+        assert_eq!(a.hash("gedalia"), a.clone().hash("gedalia"));
+    }
+}
@@ -0,0 +1,258 @@
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use crate::adaptive::AdaptiveLoadFactor;
+use crate::{
+    buckets_for, BoxedHashTable, DefaultSimpleHasher, HashTable, SimpleHasher, MAX_LOAD_FACTOR,
+};
+
+/// Collects a table's configuration and builds it in one go. Options left unset keep the defaults
+/// `HashTable::new` uses.
+pub struct HashTableBuilder<K, V, H = DefaultSimpleHasher> {
+    capacity: Option<usize>,
+    hasher: H,
+    load_factor: f64,
+    adaptive_load_factor: Option<(f64, f64)>,
+    max_chain_length: Option<usize>,
+    #[cfg(feature = "probe-length-assertions")]
+    max_probe_length: Option<usize>,
+    // ties the builder to the table it builds so `HashTable::builder()` can infer its types
+    marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> HashTableBuilder<K, V, DefaultSimpleHasher> {
+    pub(crate) fn new() -> HashTableBuilder<K, V, DefaultSimpleHasher> {
+        HashTableBuilder {
+            capacity: None,
+            hasher: DefaultSimpleHasher::new(),
+            load_factor: MAX_LOAD_FACTOR,
+            adaptive_load_factor: None,
+            max_chain_length: None,
+            #[cfg(feature = "probe-length-assertions")]
+            max_probe_length: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V, H> HashTableBuilder<K, V, H> {
+    /// How many entries the table holds before it first grows, as with `HashTable::with_capacity`,
+    /// at the load factor it starts with.
+    pub fn capacity(mut self, capacity: usize) -> HashTableBuilder<K, V, H> {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    // the same configuration, for a table holding values of another type
+    pub(crate) fn values<V2>(self) -> HashTableBuilder<K, V2, H> {
+        HashTableBuilder {
+            capacity: self.capacity,
+            hasher: self.hasher,
+            load_factor: self.load_factor,
+            adaptive_load_factor: self.adaptive_load_factor,
+            max_chain_length: self.max_chain_length,
+            #[cfg(feature = "probe-length-assertions")]
+            max_probe_length: self.max_probe_length,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn hasher<H2>(self, hasher: H2) -> HashTableBuilder<K, V, H2> {
+        HashTableBuilder {
+            capacity: self.capacity,
+            hasher,
+            load_factor: self.load_factor,
+            adaptive_load_factor: self.adaptive_load_factor,
+            max_chain_length: self.max_chain_length,
+            #[cfg(feature = "probe-length-assertions")]
+            max_probe_length: self.max_probe_length,
+            marker: PhantomData,
+        }
+    }
+
+    /// The number of entries per bucket the table grows past. Chains let this go above one, trading
+    /// longer probes for fewer buckets. Panics unless `load_factor` is positive and finite.
+    pub fn load_factor(mut self, load_factor: f64) -> HashTableBuilder<K, V, H> {
+        assert!(
+            load_factor > 0.0 && load_factor.is_finite(),
+            "a load factor must be positive and finite"
+        );
+        self.load_factor = load_factor;
+        self
+    }
+
+    /// Lets the table move its load factor between `min` and `max` on its own, lowering it while
+    /// lookups compare against many keys and raising it while they compare against few, allowing for
+    /// misses comparing against more keys than hits do. The table
+    /// starts from `load_factor`, or the default, clamped into these bounds. Panics unless
+    /// `0 < min <= max` and both are finite.
+    pub fn adaptive_load_factor(mut self, min: f64, max: f64) -> HashTableBuilder<K, V, H> {
+        assert!(
+            min > 0.0 && min <= max && max.is_finite(),
+            "adaptive load factor bounds must satisfy 0 < min <= max"
+        );
+        self.adaptive_load_factor = Some((min, max));
+        self
+    }
+
+    /// See `HashTable::with_max_chain_length`.
+    pub fn max_chain_length(mut self, max_chain_length: usize) -> HashTableBuilder<K, V, H> {
+        self.max_chain_length = Some(max_chain_length);
+        self
+    }
+
+    /// See `HashTable::with_max_probe_length`.
+    #[cfg(feature = "probe-length-assertions")]
+    pub fn max_probe_length(mut self, max_probe_length: usize) -> HashTableBuilder<K, V, H> {
+        self.max_probe_length = Some(max_probe_length);
+        self
+    }
+
+    pub fn build(self) -> HashTable<K, V, H>
+    where
+        K: Hash,
+        H: SimpleHasher<K>,
+    {
+        let adaptive = self
+            .adaptive_load_factor
+            .map(|(min, max)| AdaptiveLoadFactor::new(min, max));
+        let load_factor = match &adaptive {
+            Some(adaptive) => adaptive.clamp(self.load_factor),
+            None => self.load_factor,
+        };
+        // unsized builds get the same 10 buckets `HashTable::new` starts with
+        let number_of_buckets = self
+            .capacity
+            .map_or(10, |capacity| buckets_for(capacity, load_factor));
+
+        let mut hash_table = HashTable::from_parts(number_of_buckets, self.hasher);
+        hash_table.max_load_factor = load_factor;
+        hash_table.adaptive_load_factor = adaptive;
+        hash_table.max_chain_length = self.max_chain_length;
+        #[cfg(feature = "probe-length-assertions")]
+        {
+            hash_table.max_probe_length = self.max_probe_length;
+        }
+        hash_table
+    }
+
+    /// Builds a `BoxedHashTable`, which keeps values behind a box when `V` is bigger than
+    /// `max_inline_size` bytes.
+    pub fn build_boxed(self, max_inline_size: usize) -> BoxedHashTable<K, V, H>
+    where
+        K: Hash + PartialEq,
+        H: SimpleHasher<K>,
+    {
+        BoxedHashTable::from_builder(self, max_inline_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HashTable, SimpleHasher};
+
+    #[test]
+    fn test_builder() {
+        struct IdentityHasher;
+        impl SimpleHasher<u64> for IdentityHasher {
+            fn hash(&self, k: &u64) -> u64 {
+                *k
+            }
+        }
+
+        let mut hash_table = HashTable::builder()
+            .capacity(8)
+            .hasher(IdentityHasher {})
+            .load_factor(2.0)
+            .build();
+        for k in 0..8 {
+            hash_table.insert(k, k * 10);
+        }
+        // two entries per bucket is still within the load factor
+        assert_eq!(hash_table.capacity(), 4);
+
+        hash_table.insert(8, 80);
+        assert_eq!(hash_table.capacity(), 8);
+        for k in 0..9 {
+            assert_eq!(hash_table.get(&k), Some(&(k * 10)));
+        }
+
+        let defaults: HashTable<&str, i32> = HashTable::builder().build();
+        assert_eq!(
+            defaults.capacity(),
+            HashTable::<&str, i32>::new().capacity()
+        );
+    }
+
+    #[test]
+    fn test_adaptive_load_factor() {
+        struct IdentityHasher;
+        impl SimpleHasher<u64> for IdentityHasher {
+            fn hash(&self, k: &u64) -> u64 {
+                *k
+            }
+        }
+
+        // chains of four make every lookup compare against 2.5 keys on average
+        let mut hash_table = HashTable::builder()
+            .capacity(32)
+            .hasher(IdentityHasher {})
+            .load_factor(4.0)
+            .adaptive_load_factor(0.5, 4.0)
+            .build();
+        for k in 0..32 {
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.capacity(), 8);
+        for _ in 0..10 {
+            for k in 0..32 {
+                hash_table.get(&k);
+            }
+        }
+        hash_table.insert(32, 32);
+        assert!(hash_table.load_factor() < 4.0);
+        assert!(hash_table.capacity() > 8);
+
+        for k in 0..33 {
+            assert_eq!(hash_table.get(&k), Some(&k));
+        }
+
+        // misses on empty buckets compare against nothing, so a sparse table's load factor climbs to its maximum
+        let mut hash_table = HashTable::builder()
+            .capacity(512)
+            .hasher(IdentityHasher {})
+            .load_factor(0.5)
+            .adaptive_load_factor(0.5, 4.0)
+            .build();
+        for k in 0..100 {
+            for missing in 512..768 {
+                hash_table.get(&missing);
+            }
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.load_factor(), 4.0);
+
+        // chains of one are short for hits but about right for misses, which walk all of them
+        let build = || {
+            let mut hash_table = HashTable::builder()
+                .capacity(8)
+                .hasher(IdentityHasher {})
+                .load_factor(1.0)
+                .adaptive_load_factor(0.5, 4.0)
+                .build();
+            for k in 0..8 {
+                hash_table.insert(k, k);
+            }
+            hash_table
+        };
+        let (mut hits, mut misses) = (build(), build());
+        for k in 0..300 {
+            assert!(hits.get(&(k % 8)).is_some());
+            assert!(misses.get(&(k % 8 + 8)).is_none());
+        }
+        hits.insert(100, 100);
+        misses.insert(100, 100);
+        assert!(hits.load_factor() > 1.0);
+        assert_eq!(misses.load_factor(), 1.0);
+    }
+}
@@ -0,0 +1,205 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::Hash;
+
+use crate::hash_index::HashIndex;
+use crate::{DefaultSimpleHasher, SimpleHasher, MAX_LOAD_FACTOR};
+
+/// A hash table that stores keys and values in separate arrays, so probing for a key only touches key
+/// memory. Pays off when values are large and lookups are dominated by `contains_key` or key scans.
+///
+/// The same hash index `HashTable` uses finds positions in the two arrays, which are kept dense:
+/// removing an entry moves the last one into its place.
+pub struct ColumnarHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    keys: Vec<K>,
+    values: Vec<V>,
+    index: HashIndex,
+    hasher: H,
+}
+
+impl<K, V> Default for ColumnarHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        ColumnarHashTable::from_parts(10, DefaultSimpleHasher::new())
+    }
+}
+
+impl<K, V> ColumnarHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> ColumnarHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> ColumnarHashTable<K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    fn from_parts(number_of_buckets: usize, hasher: H) -> ColumnarHashTable<K, V, H> {
+        ColumnarHashTable {
+            keys: vec![],
+            values: vec![],
+            index: HashIndex::new(number_of_buckets),
+            hasher,
+        }
+    }
+}
+
+impl<K, V, H> ColumnarHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> ColumnarHashTable<K, V, H> {
+        ColumnarHashTable::from_parts(10, hasher)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let hash = self.hasher.hash(&k);
+        if let Some(slot) = self.find(hash, &k) {
+            return Some(core::mem::replace(&mut self.values[slot], v));
+        }
+
+        let number_of_buckets = self.index.number_of_buckets();
+        let new_load_factor = (self.keys.len() + 1) as f64 / number_of_buckets as f64;
+        if new_load_factor > MAX_LOAD_FACTOR {
+            self.index
+                .resize(number_of_buckets * 2, key_order(&self.keys, &self.hasher));
+        }
+
+        self.keys.push(k);
+        self.values.push(v);
+        self.index.push(hash, key_order(&self.keys, &self.hasher));
+        None
+    }
+
+    /// Only reads keys, never values.
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.find(self.hasher.hash(k), k).is_some()
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let slot = self.find(self.hasher.hash(k), k)?;
+        Some(&self.values[slot])
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let slot = self.find(self.hasher.hash(k), k)?;
+        Some(&mut self.values[slot])
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let slot = self.find(self.hasher.hash(k), k)?;
+        self.index
+            .swap_remove(slot, key_order(&self.keys, &self.hasher));
+        self.keys.swap_remove(slot);
+        Some(self.values.swap_remove(slot))
+    }
+
+    /// Every key, without touching values. In no particular order, but the same order as `values`.
+    pub fn keys(&self) -> core::slice::Iter<'_, K> {
+        self.keys.iter()
+    }
+
+    pub fn values(&self) -> core::slice::Iter<'_, V> {
+        self.values.iter()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    // the position of `k` in the key and value arrays
+    fn find(&self, hash: u64, k: &K) -> Option<usize> {
+        let (keys, hasher) = (&self.keys, &self.hasher);
+        let (found, _) = self.index.find(
+            hash,
+            |slot| hasher.key_eq(&keys[slot], k),
+            |slot| hasher.key_cmp(&keys[slot], k),
+        );
+        found
+    }
+}
+
+// orders the keys at two positions, as `HashIndex` needs
+fn key_order<'a, K, H>(
+    keys: &'a [K],
+    hasher: &'a H,
+) -> impl Fn(usize, usize) -> Option<Ordering> + 'a
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    move |a, b| hasher.key_cmp(&keys[a], &keys[b])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ColumnarHashTable, SimpleHasher};
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut hash_table = ColumnarHashTable::new();
+        for k in 0..100 {
+            assert_eq!(hash_table.insert(k, k * 10), None);
+        }
+        assert_eq!(hash_table.insert(7, 0), Some(70));
+        assert_eq!(hash_table.len(), 100);
+
+        for k in (0..100).step_by(3) {
+            assert_eq!(hash_table.remove(&k), Some(if k == 7 { 0 } else { k * 10 }));
+        }
+        assert_eq!(hash_table.remove(&3), None);
+        for k in 0..100 {
+            assert_eq!(hash_table.contains_key(&k), k % 3 != 0);
+        }
+        for (k, v) in hash_table.iter() {
+            assert_eq!(hash_table.get(k), Some(v));
+        }
+
+        let mut keys: Vec<_> = hash_table.keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys, (0..100).filter(|k| k % 3 != 0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_remove_from_long_chains() {
+        // two hashes put every key in one of two long chains
+        struct TwoHashes;
+        impl SimpleHasher<u32> for TwoHashes {
+            fn hash(&self, k: &u32) -> u64 {
+                u64::from(k % 2)
+            }
+        }
+        let mut hash_table = ColumnarHashTable::with_hasher(TwoHashes);
+        for k in 0..50 {
+            hash_table.insert(k, k + 1);
+        }
+        for k in (0..50).filter(|k| k % 3 == 0) {
+            assert_eq!(hash_table.remove(&k), Some(k + 1));
+        }
+        for k in 0..50 {
+            let expected = if k % 3 == 0 { None } else { Some(&(k + 1)) };
+            assert_eq!(hash_table.get(&k), expected);
+        }
+        assert_eq!(hash_table.len(), 33);
+    }
+}
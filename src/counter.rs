@@ -0,0 +1,165 @@
+use std::hash::Hash;
+
+use crate::sharded::{read, write, DEFAULT_NUMBER_OF_SHARDS};
+use crate::sync::{AtomicU64, Ordering, RwLock};
+use crate::{DefaultSimpleHasher, Entry, HashTable, SimpleHasher};
+
+/// A table of `u64` counters that can be shared between threads.
+///
+/// Counters are atomics, so incrementing a key that already exists only takes its shard's read lock
+/// and never blocks other increments. Only adding and removing keys takes the write lock.
+pub struct AtomicCounterTable<K, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    shards: Vec<RwLock<HashTable<K, AtomicU64, H>>>,
+    hasher: H,
+}
+
+impl<K> Default for AtomicCounterTable<K, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        AtomicCounterTable::with_shards(DEFAULT_NUMBER_OF_SHARDS)
+    }
+}
+
+impl<K> AtomicCounterTable<K, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> AtomicCounterTable<K, DefaultSimpleHasher> {
+        Default::default()
+    }
+
+    pub fn with_shards(number_of_shards: usize) -> AtomicCounterTable<K, DefaultSimpleHasher> {
+        AtomicCounterTable::with_shards_and_hasher(number_of_shards, DefaultSimpleHasher::new())
+    }
+}
+
+impl<K, H> AtomicCounterTable<K, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K> + Clone,
+{
+    /// Panics if `number_of_shards` is zero.
+    pub fn with_shards_and_hasher(number_of_shards: usize, hasher: H) -> AtomicCounterTable<K, H> {
+        assert!(
+            number_of_shards > 0,
+            "a counter table needs at least one shard"
+        );
+        let mut shards = vec![];
+        for _ in 0..number_of_shards {
+            shards.push(RwLock::new(HashTable::with_hasher(hasher.clone())));
+        }
+
+        AtomicCounterTable { shards, hasher }
+    }
+}
+
+impl<K, H> AtomicCounterTable<K, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    /// Adds `delta` to the counter for `k`, starting it at zero if it is missing. Returns the new count,
+    /// wrapping on overflow.
+    pub fn increment(&self, k: &K, delta: u64) -> u64
+    where
+        K: Clone,
+    {
+        let shard = self.shard(k);
+        if let Some(count) = read(shard).get(k) {
+            return count
+                .fetch_add(delta, Ordering::Relaxed)
+                .wrapping_add(delta);
+        }
+
+        // another thread may have added the key between dropping the read lock and taking this one
+        match write(shard).entry(k.clone()) {
+            Entry::Occupied(e) => e
+                .get()
+                .fetch_add(delta, Ordering::Relaxed)
+                .wrapping_add(delta),
+            Entry::Vacant(e) => {
+                e.insert_entry(AtomicU64::new(delta));
+                delta
+            }
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<u64> {
+        read(self.shard(k))
+            .get(k)
+            .map(|count| count.load(Ordering::Relaxed))
+    }
+
+    pub fn remove(&self, k: &K) -> Option<u64> {
+        write(self.shard(k)).remove(k).map(AtomicU64::into_inner)
+    }
+
+    fn shard(&self, k: &K) -> &RwLock<HashTable<K, AtomicU64, H>> {
+        // the same rule `ShardedHashTable` uses
+        let hash = self.hasher.hash(k);
+        &self.shards[(hash >> 32) as usize % self.shards.len()]
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use crate::AtomicCounterTable;
+
+    #[test]
+    fn test_concurrent_increments() {
+        let counters = AtomicCounterTable::new();
+        let number_of_threads = 8;
+
+        thread::scope(|s| {
+            for _ in 0..number_of_threads {
+                s.spawn(|| {
+                    for i in 0..1000u64 {
+                        counters.increment(&(i % 10), 1);
+                    }
+                });
+            }
+        });
+
+        for k in 0..10 {
+            assert_eq!(counters.get(&k), Some(100 * number_of_threads));
+        }
+        assert_eq!(counters.increment(&3, 5), 805);
+        assert_eq!(counters.remove(&3), Some(805));
+        assert_eq!(counters.get(&3), None);
+        assert_eq!(counters.increment(&3, 2), 2);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::sync::Arc;
+    use loom::thread;
+
+    use crate::AtomicCounterTable;
+
+    #[test]
+    fn loom_increments_of_a_missing_key_are_not_lost() {
+        loom::model(|| {
+            let counters = Arc::new(AtomicCounterTable::with_shards(1));
+
+            let other = {
+                let counters = Arc::clone(&counters);
+                thread::spawn(move || counters.increment(&"gedalia", 1))
+            };
+            let mine = counters.increment(&"gedalia", 2);
+            let theirs = other.join().unwrap();
+
+            // each saw either only its own delta or both of them
+            assert!((mine, theirs) == (2, 3) || (mine, theirs) == (3, 1));
+            assert_eq!(counters.get(&"gedalia"), Some(3));
+        });
+    }
+}
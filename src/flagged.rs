@@ -0,0 +1,125 @@
+use core::hash::Hash;
+
+use crate::{DefaultSimpleHasher, Entry, HashTable, SimpleHasher};
+
+/// A hash table that keeps a metadata byte next to every entry, for things like mark bits, without
+/// widening the value type or keeping a second table of keys.
+///
+/// New entries start with a flag of zero.
+pub struct FlaggedHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, (V, u8), H>,
+}
+
+impl<K, V> Default for FlaggedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        FlaggedHashTable {
+            inner: Default::default(),
+        }
+    }
+}
+
+impl<K, V> FlaggedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> FlaggedHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> FlaggedHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> FlaggedHashTable<K, V, H> {
+        FlaggedHashTable {
+            inner: HashTable::with_hasher(hasher),
+        }
+    }
+
+    /// Replacing the value of an existing key keeps its flag.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        match self.inner.entry(k) {
+            Entry::Occupied(mut e) => Some(core::mem::replace(&mut e.get_mut().0, v)),
+            Entry::Vacant(e) => {
+                e.insert_entry((v, 0));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k).map(|(v, _)| v)
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.inner.get_mut(k).map(|(v, _)| v)
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        self.inner.remove(k).map(|(v, _)| v)
+    }
+
+    pub fn get_flag(&self, k: &K) -> Option<u8> {
+        self.inner.get(k).map(|&(_, flag)| flag)
+    }
+
+    /// Sets the flag of `k`, returning the old one, or `None` without changing anything if `k` is missing.
+    pub fn set_flag(&mut self, k: &K, flag: u8) -> Option<u8> {
+        self.inner
+            .get_mut(k)
+            .map(|(_, old)| core::mem::replace(old, flag))
+    }
+
+    /// Resets every entry's flag to zero.
+    pub fn clear_flags(&mut self) {
+        for (_, flag) in self.inner.values_mut() {
+            *flag = 0;
+        }
+    }
+
+    /// Removes every entry whose flag is zero, returning how many were removed.
+    pub fn sweep_unflagged(&mut self) -> usize {
+        self.inner.retain_entries(|_, &mut (_, flag)| flag != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FlaggedHashTable;
+
+    #[test]
+    fn test_mark_and_sweep() {
+        let mut hash_table = FlaggedHashTable::new();
+        for (i, name) in ["gedalia", "theo", "aviva", "chani"].iter().enumerate() {
+            hash_table.insert(*name, i);
+        }
+        assert_eq!(hash_table.get_flag(&"theo"), Some(0));
+
+        assert_eq!(hash_table.set_flag(&"gedalia", 1), Some(0));
+        assert_eq!(hash_table.set_flag(&"aviva", 7), Some(0));
+        assert_eq!(hash_table.set_flag(&"nowhereman", 1), None);
+        // replacing a value keeps its flag
+        assert_eq!(hash_table.insert("aviva", 26), Some(2));
+        assert_eq!(hash_table.get_flag(&"aviva"), Some(7));
+
+        assert_eq!(hash_table.sweep_unflagged(), 2);
+        assert_eq!(hash_table.get(&"gedalia"), Some(&0));
+        assert_eq!(hash_table.get(&"aviva"), Some(&26));
+        assert_eq!(hash_table.get(&"theo"), None);
+        assert_eq!(hash_table.get(&"chani"), None);
+
+        hash_table.clear_flags();
+        assert_eq!(hash_table.get_flag(&"gedalia"), Some(0));
+        assert_eq!(hash_table.sweep_unflagged(), 2);
+        assert_eq!(hash_table.get(&"gedalia"), None);
+    }
+}
@@ -0,0 +1,143 @@
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// A float type `FloatKey` can wrap. Only implemented for `f32` and `f64`.
+pub trait Float: Copy + private::Sealed {
+    #[doc(hidden)]
+    fn canonical_bits(self) -> u64;
+    #[doc(hidden)]
+    fn canonical_cmp(self, other: Self) -> Ordering;
+}
+
+impl Float for f32 {
+    fn canonical_bits(self) -> u64 {
+        u64::from(canonical_f32(self).to_bits())
+    }
+
+    fn canonical_cmp(self, other: f32) -> Ordering {
+        canonical_f32(self).total_cmp(&canonical_f32(other))
+    }
+}
+
+impl Float for f64 {
+    fn canonical_bits(self) -> u64 {
+        canonical_f64(self).to_bits()
+    }
+
+    fn canonical_cmp(self, other: f64) -> Ordering {
+        canonical_f64(self).total_cmp(&canonical_f64(other))
+    }
+}
+
+// every NaN becomes the same NaN and negative zero becomes zero
+fn canonical_f32(f: f32) -> f32 {
+    if f.is_nan() {
+        f32::NAN
+    } else if f == 0.0 {
+        0.0
+    } else {
+        f
+    }
+}
+
+fn canonical_f64(f: f64) -> f64 {
+    if f.is_nan() {
+        f64::NAN
+    } else if f == 0.0 {
+        0.0
+    } else {
+        f
+    }
+}
+
+/// Wraps a float so it can be used as a hash table key.
+///
+/// Floats aren't `Eq` because NaN isn't equal to itself, which would leave entries keyed by NaN
+/// impossible to find. `FloatKey` instead treats every NaN as equal to every other NaN, and `0.0` as
+/// equal to `-0.0` as IEEE 754 does. Ordering follows the same rules, with NaN above everything else.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FloatKey<T>(pub T);
+
+impl<T: Float> FloatKey<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Float> From<T> for FloatKey<T> {
+    fn from(t: T) -> FloatKey<T> {
+        FloatKey(t)
+    }
+}
+
+impl<T: Float> PartialEq for FloatKey<T> {
+    fn eq(&self, other: &FloatKey<T>) -> bool {
+        self.0.canonical_bits() == other.0.canonical_bits()
+    }
+}
+
+impl<T: Float> Eq for FloatKey<T> {}
+
+impl<T: Float> Hash for FloatKey<T> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.0.canonical_bits().hash(state);
+    }
+}
+
+impl<T: Float> PartialOrd for FloatKey<T> {
+    fn partial_cmp(&self, other: &FloatKey<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Float> Ord for FloatKey<T> {
+    fn cmp(&self, other: &FloatKey<T>) -> Ordering {
+        self.0.canonical_cmp(other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FloatKey, HashTable};
+
+    #[test]
+    fn test_float_keys() {
+        let mut hash_table = HashTable::new();
+        hash_table.insert(FloatKey(1.5f64), "one and a half");
+        hash_table.insert(FloatKey(f64::NAN), "nan");
+        hash_table.insert(FloatKey(-0.0), "zero");
+
+        assert_eq!(hash_table.get(&FloatKey(1.5)), Some(&"one and a half"));
+        // NaNs with different payloads and signs are the same key
+        assert_eq!(hash_table.get(&FloatKey(-f64::NAN)), Some(&"nan"));
+        assert_eq!(
+            hash_table.get(&FloatKey(f64::from_bits(0x7ff8_0000_0000_0001))),
+            Some(&"nan")
+        );
+        assert_eq!(hash_table.get(&FloatKey(0.0)), Some(&"zero"));
+        assert_eq!(hash_table.get(&FloatKey(2.0)), None);
+
+        let mut keys = [
+            FloatKey(f32::NAN),
+            FloatKey(1.0),
+            FloatKey(f32::NEG_INFINITY),
+            FloatKey(-0.0),
+            FloatKey(0.0),
+        ];
+        keys.sort();
+        assert_eq!(
+            keys.iter()
+                .map(|k| k.into_inner())
+                .take(4)
+                .collect::<Vec<_>>(),
+            vec![f32::NEG_INFINITY, 0.0, 0.0, 1.0]
+        );
+        assert!(keys[4].into_inner().is_nan());
+    }
+}
@@ -0,0 +1,71 @@
+use core::hash::{Hash, Hasher};
+
+use crate::SimpleHasher;
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A `SimpleHasher` using 64-bit FNV-1a, implemented here without any dependencies.
+///
+/// FNV is fast for short keys but isn't keyed, so tables using it can be flooded with collisions by
+/// anyone choosing their keys. Prefer `DefaultSimpleHasher` for untrusted input.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FnvSimpleHasher;
+
+impl FnvSimpleHasher {
+    pub const fn new() -> FnvSimpleHasher {
+        FnvSimpleHasher
+    }
+}
+
+impl<K: Hash + ?Sized> SimpleHasher<K> for FnvSimpleHasher {
+    fn hash(&self, t: &K) -> u64 {
+        let mut s = FnvHasher(OFFSET_BASIS);
+        t.hash(&mut s);
+        s.finish()
+    }
+}
+
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+
+    use super::{FnvHasher, OFFSET_BASIS};
+    use crate::{FnvSimpleHasher, HashTable};
+
+    #[test]
+    fn test_fnv1a() {
+        // reference values from the FNV specification
+        for (bytes, hash) in [
+            (&b""[..], 0xcbf2_9ce4_8422_2325),
+            (&b"a"[..], 0xaf63_dc4c_8601_ec8c),
+            (&b"foobar"[..], 0x8594_4171_f739_67e8),
+        ] {
+            let mut s = FnvHasher(OFFSET_BASIS);
+            s.write(bytes);
+            assert_eq!(s.finish(), hash);
+        }
+
+        const HASHER: FnvSimpleHasher = FnvSimpleHasher::new();
+        let mut hash_table = HashTable::with_hasher(HASHER);
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("theo", 0);
+        assert_eq!(hash_table.get(&"gedalia"), Some(&27));
+        assert_eq!(hash_table.get(&"theo"), Some(&0));
+    }
+}
@@ -0,0 +1,534 @@
+use alloc::collections::TryReserveError;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+// chains longer than this are kept as a list of their entries ordered by key hash, then by key where
+// the hasher can order keys, so probes can binary search them. once a chain drops back to this length
+// it is linked and scanned linearly again
+const SORTED_CHAIN_THRESHOLD: usize = 8;
+
+// ends a linked chain
+const NO_ENTRY: usize = usize::MAX;
+
+// finds entries by hash for the tables that keep them packed in a vector, `HashTable` among them.
+// the index knows nothing of keys: entries are positions in the table's vector, and wherever keys have
+// to be compared the table passes closures that do it by position, `key_eq` and `key_cmp` against a key
+// being looked up and `key_order` between two keys already in the table. every position up to the
+// vector's length is indexed, so the table updates the index along with its vector
+#[derive(Clone)]
+pub(crate) struct HashIndex {
+    // the hash of each entry's key, so probes and resizes never have to rehash
+    hashes: Vec<u64>,
+    // for each entry, the next entry in its bucket's chain while that chain is linked
+    links: Vec<usize>,
+    buckets: Vec<Chain>,
+}
+
+// the entries of one bucket, as positions in the table's vector. short chains are linked through
+// `HashIndex::links` so buckets need no allocation of their own, long ones list their entries in order
+#[derive(Clone)]
+enum Chain {
+    Linked { head: usize, len: usize },
+    Sorted(Vec<usize>),
+}
+
+impl Chain {
+    const EMPTY: Chain = Chain::Linked {
+        head: NO_ENTRY,
+        len: 0,
+    };
+
+    fn len(&self) -> usize {
+        match self {
+            Chain::Linked { len, .. } => *len,
+            Chain::Sorted(sorted) => sorted.len(),
+        }
+    }
+
+    fn indices<'a>(&'a self, links: &'a [usize]) -> ChainIndices<'a> {
+        match self {
+            Chain::Linked { head, .. } => ChainIndices::Linked { next: *head, links },
+            Chain::Sorted(sorted) => ChainIndices::Sorted(sorted.iter()),
+        }
+    }
+
+    // puts entry `index` at the front of a linked chain, whatever its length
+    fn link(&mut self, links: &mut [usize], index: usize) {
+        match self {
+            Chain::Linked { head, len } => {
+                links[index] = *head;
+                *head = index;
+                *len += 1;
+            }
+            Chain::Sorted(_) => unreachable!("only linked chains are linked"),
+        }
+    }
+}
+
+// walks the entry indices of a chain in order
+pub(crate) enum ChainIndices<'a> {
+    Linked { next: usize, links: &'a [usize] },
+    Sorted(core::slice::Iter<'a, usize>),
+}
+
+impl Iterator for ChainIndices<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match self {
+            ChainIndices::Linked { next, links } => {
+                let index = *next;
+                if index == NO_ENTRY {
+                    return None;
+                }
+                *next = links[index];
+                Some(index)
+            }
+            ChainIndices::Sorted(sorted) => sorted.next().copied(),
+        }
+    }
+}
+
+// whatever in a chain refers to an entry: the head of a linked chain, the link of the entry before it,
+// or a slot in a sorted chain
+#[derive(Clone, Copy)]
+enum Referrer {
+    Head(usize),
+    Link(usize),
+    Slot(usize, usize),
+}
+
+impl HashIndex {
+    pub(crate) fn new(number_of_buckets: usize) -> HashIndex {
+        HashIndex {
+            hashes: vec![],
+            links: vec![],
+            buckets: vec![Chain::EMPTY; number_of_buckets],
+        }
+    }
+
+    pub(crate) fn number_of_buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub(crate) fn bucket_of(&self, hash: u64) -> usize {
+        hash as usize % self.buckets.len()
+    }
+
+    // every entry's hash, by position
+    pub(crate) fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn chain(&self, bucket_index: usize) -> ChainIndices<'_> {
+        self.buckets[bucket_index].indices(&self.links)
+    }
+
+    // every bucket's chain, including empty ones
+    pub(crate) fn chains(&self) -> impl Iterator<Item = ChainIndices<'_>> {
+        self.buckets
+            .iter()
+            .map(move |chain| chain.indices(&self.links))
+    }
+
+    #[cfg(feature = "probe-length-assertions")]
+    pub(crate) fn chain_lengths(&self) -> impl Iterator<Item = usize> + '_ {
+        self.buckets.iter().map(Chain::len)
+    }
+
+    // indexes the entry just pushed onto the end of the table's vector, whose key hashes to `hash`
+    pub(crate) fn push<O>(&mut self, hash: u64, key_order: O)
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        let index = self.hashes.len();
+        self.hashes.push(hash);
+        self.links.push(NO_ENTRY);
+        let bucket_index = self.bucket_of(hash);
+        let (hashes, links) = (&self.hashes, &mut self.links);
+        match &mut self.buckets[bucket_index] {
+            Chain::Sorted(sorted) => {
+                // unordered keys go after the others sharing their hash
+                let position = sorted.partition_point(|&i| match hashes[i].cmp(&hash) {
+                    Ordering::Equal => key_order(i, index) != Some(Ordering::Greater),
+                    ordering => ordering == Ordering::Less,
+                });
+                sorted.insert(position, index);
+            }
+            chain => {
+                chain.link(links, index);
+                if chain.len() > SORTED_CHAIN_THRESHOLD {
+                    // the chain just became long so it has to be put in order
+                    *chain = sort_chain(chain, hashes, links, &key_order);
+                }
+            }
+        }
+    }
+
+    // the entry whose key hashes to `hash` and satisfies `key_eq`, and how many keys were compared
+    // against to get the answer. `key_cmp` orders an entry's key against the one being looked for
+    pub(crate) fn find<E, C>(&self, hash: u64, key_eq: E, key_cmp: C) -> (Option<usize>, usize)
+    where
+        E: Fn(usize) -> bool,
+        C: Fn(usize) -> Option<Ordering>,
+    {
+        let sorted = match &self.buckets[self.bucket_of(hash)] {
+            Chain::Sorted(sorted) => sorted,
+            chain => {
+                let mut probe_length = 0;
+                for index in chain.indices(&self.links) {
+                    probe_length += 1;
+                    // keys can only be equal if their hashes are, which is far cheaper to check
+                    if self.hashes[index] == hash && key_eq(index) {
+                        return (Some(index), probe_length);
+                    }
+                }
+                return (None, probe_length);
+            }
+        };
+
+        // long chains are ordered by hash so binary search for the run of entries sharing this hash
+        let start = sorted.partition_point(|&index| self.hashes[index] < hash);
+        let run = &sorted[start..];
+        let run = &run[..run.partition_point(|&index| self.hashes[index] == hash)];
+
+        // which is ordered by key too if the hasher can order keys
+        let (mut low, mut high) = (0, run.len());
+        let mut probe_length = 0;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let ordering = match key_cmp(run[middle]) {
+                Some(ordering) => ordering,
+                None => break,
+            };
+            probe_length += 1;
+            match ordering {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return (Some(run[middle]), probe_length),
+            }
+        }
+        if low == high {
+            return (None, probe_length);
+        }
+
+        // and has to be scanned when it can't
+        for (position, &index) in run.iter().enumerate() {
+            if key_eq(index) {
+                return (Some(index), position + 1);
+            }
+        }
+        (None, run.len())
+    }
+
+    // drops entry `index` and moves the last entry into its position, as `Vec::swap_remove` does
+    pub(crate) fn swap_remove<O>(&mut self, index: usize, key_order: O)
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        self.unlink(index, &key_order);
+        let last = self.hashes.len() - 1;
+        if index != last {
+            let referrer = self.referrer(last, &key_order);
+            self.point(referrer, index);
+        }
+        self.links.swap_remove(index);
+        self.hashes.swap_remove(index);
+    }
+
+    // drops entry `index` and shifts every later entry down a position, as `Vec::remove` does
+    pub(crate) fn shift_remove<O>(&mut self, index: usize, key_order: O)
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        self.unlink(index, &key_order);
+        self.links.remove(index);
+        self.hashes.remove(index);
+        self.renumber(|i| if i > index { i - 1 } else { i });
+    }
+
+    // moves entry `from` to position `to`, shifting the entries in between
+    pub(crate) fn move_index(&mut self, from: usize, to: usize) {
+        self.renumber(|i| {
+            if i == from {
+                to
+            } else if from < i && i <= to {
+                i - 1
+            } else if to <= i && i < from {
+                i + 1
+            } else {
+                i
+            }
+        });
+        if from < to {
+            self.links[from..=to].rotate_left(1);
+            self.hashes[from..=to].rotate_left(1);
+        } else {
+            self.links[to..=from].rotate_right(1);
+            self.hashes[to..=from].rotate_right(1);
+        }
+    }
+
+    // swaps the positions of entries `a` and `b`, which must differ
+    pub(crate) fn swap<O>(&mut self, a: usize, b: usize, key_order: O)
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        let (referrer_a, referrer_b) = (self.referrer(a, &key_order), self.referrer(b, &key_order));
+        // a link that refers to one of the two moves along with it below, which keeps it right
+        self.point(referrer_a, b);
+        self.point(referrer_b, a);
+        self.links.swap(a, b);
+        self.hashes.swap(a, b);
+    }
+
+    fn unlink<O>(&mut self, index: usize, key_order: &O)
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        let bucket_index = self.bucket_of(self.hashes[index]);
+        let referrer = self.referrer(index, key_order);
+        let sorted = match (&mut self.buckets[bucket_index], referrer) {
+            (Chain::Sorted(sorted), Referrer::Slot(_, position)) => {
+                sorted.remove(position);
+                sorted
+            }
+            (chain, referrer) => {
+                if let Chain::Linked { len, .. } = chain {
+                    *len -= 1;
+                }
+                let next = self.links[index];
+                self.point(referrer, next);
+                return;
+            }
+        };
+
+        // long chains that are no longer long get linked again
+        if sorted.len() <= SORTED_CHAIN_THRESHOLD {
+            let mut chain = Chain::EMPTY;
+            for &i in sorted.iter().rev() {
+                chain.link(&mut self.links, i);
+            }
+            self.buckets[bucket_index] = chain;
+        }
+    }
+
+    fn referrer<O>(&self, index: usize, key_order: &O) -> Referrer
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        let bucket_index = self.bucket_of(self.hashes[index]);
+        match &self.buckets[bucket_index] {
+            Chain::Linked { head, .. } if *head == index => Referrer::Head(bucket_index),
+            Chain::Linked { head, .. } => {
+                let mut previous = *head;
+                while self.links[previous] != index {
+                    previous = self.links[previous];
+                }
+                Referrer::Link(previous)
+            }
+            Chain::Sorted(sorted) => {
+                // long chains are binary searched by hash then key, as probes do, so a pathological
+                // chain costs a removal no more comparisons than a lookup
+                let hash = self.hashes[index];
+                let start = sorted.partition_point(|&i| match self.hashes[i].cmp(&hash) {
+                    Ordering::Equal => key_order(i, index) == Some(Ordering::Less),
+                    ordering => ordering == Ordering::Less,
+                });
+                // which lands on the entry itself, or on the start of its hash's run for keys that
+                // can't be ordered
+                let position = start
+                    + sorted[start..]
+                        .iter()
+                        .position(|&i| i == index)
+                        .expect("every entry is chained into its bucket");
+                Referrer::Slot(bucket_index, position)
+            }
+        }
+    }
+
+    // makes `referrer` refer to entry `index` instead
+    fn point(&mut self, referrer: Referrer, index: usize) {
+        match referrer {
+            Referrer::Head(bucket_index) => {
+                if let Chain::Linked { head, .. } = &mut self.buckets[bucket_index] {
+                    *head = index;
+                }
+            }
+            Referrer::Link(previous) => self.links[previous] = index,
+            Referrer::Slot(bucket_index, position) => {
+                if let Chain::Sorted(sorted) = &mut self.buckets[bucket_index] {
+                    sorted[position] = index;
+                }
+            }
+        }
+    }
+
+    // rewrites every position a chain refers to, leaving the entries' own hashes and links where they are
+    fn renumber(&mut self, f: impl Fn(usize) -> usize) {
+        for link in &mut self.links {
+            if *link != NO_ENTRY {
+                *link = f(*link);
+            }
+        }
+        for chain in &mut self.buckets {
+            match chain {
+                Chain::Linked { head, .. } => {
+                    if *head != NO_ENTRY {
+                        *head = f(*head);
+                    }
+                }
+                Chain::Sorted(sorted) => {
+                    for index in sorted.iter_mut() {
+                        *index = f(*index);
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn resize<O>(&mut self, number_of_buckets: usize, key_order: O)
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        self.rehash_into(vec![Chain::EMPTY; number_of_buckets], key_order);
+    }
+
+    // like `resize`, but returns an error instead of aborting if the buckets can't be allocated
+    pub(crate) fn try_resize<O>(
+        &mut self,
+        number_of_buckets: usize,
+        key_order: O,
+    ) -> Result<(), TryReserveError>
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        let mut new_buckets = vec![];
+        new_buckets.try_reserve_exact(number_of_buckets)?;
+        new_buckets.resize(number_of_buckets, Chain::EMPTY);
+        self.rehash_into(new_buckets, key_order);
+        Ok(())
+    }
+
+    // like `resize`, with entry `i` going into bucket `bucket_indices[i]`, which the caller has
+    // already worked out
+    #[cfg(feature = "rayon")]
+    pub(crate) fn resize_into<O>(
+        &mut self,
+        number_of_buckets: usize,
+        bucket_indices: Vec<usize>,
+        key_order: O,
+    ) where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        self.link_into(
+            vec![Chain::EMPTY; number_of_buckets],
+            bucket_indices,
+            key_order,
+        );
+    }
+
+    // chains every entry into `new_buckets`, which must all be empty, and makes them the buckets
+    fn rehash_into<O>(&mut self, new_buckets: Vec<Chain>, key_order: O)
+    where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        let number_of_buckets = new_buckets.len();
+        let bucket_indices: Vec<usize> = self
+            .hashes
+            .iter()
+            .map(|&hash| hash as usize % number_of_buckets)
+            .collect();
+        self.link_into(new_buckets, bucket_indices, key_order);
+    }
+
+    // chains entry `i` into `new_buckets[bucket_indices[i]]` and makes them the buckets
+    fn link_into<O>(
+        &mut self,
+        mut new_buckets: Vec<Chain>,
+        bucket_indices: Vec<usize>,
+        key_order: O,
+    ) where
+        O: Fn(usize, usize) -> Option<Ordering>,
+    {
+        for (index, bucket_index) in bucket_indices.into_iter().enumerate() {
+            new_buckets[bucket_index].link(&mut self.links, index);
+        }
+
+        // redistribution may have produced new long chains which need to be put in order
+        for chain in &mut new_buckets {
+            if chain.len() > SORTED_CHAIN_THRESHOLD {
+                *chain = sort_chain(chain, &self.hashes, &self.links, &key_order);
+            }
+        }
+
+        self.buckets = new_buckets;
+    }
+
+    // whether the chain `hash` falls in holds at least `max_chain_length` entries, some of which
+    // doubling the buckets would move elsewhere
+    pub(crate) fn chain_would_overflow(&self, hash: u64, max_chain_length: usize) -> bool {
+        let chain = &self.buckets[self.bucket_of(hash)];
+        if chain.len() < max_chain_length {
+            return false;
+        }
+
+        // growing only helps if doubling would actually split the chain. keys sharing a full hash
+        // (or a hasher that ignores its input) would otherwise make every insert double the table
+        let extended_number_of_buckets = self.buckets.len() * 2;
+        let new_bucket_index = hash as usize % extended_number_of_buckets;
+        chain.indices(&self.links).any(|index| {
+            self.hashes[index] as usize % extended_number_of_buckets != new_bucket_index
+        })
+    }
+
+    // forgets every entry, keeping the buckets
+    pub(crate) fn clear(&mut self) {
+        self.hashes.clear();
+        self.links.clear();
+        for chain in &mut self.buckets {
+            *chain = Chain::EMPTY;
+        }
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.hashes.reserve(additional);
+        self.links.reserve(additional);
+    }
+
+    pub(crate) fn reserve_exact(&mut self, additional: usize) {
+        self.hashes.reserve_exact(additional);
+        self.links.reserve_exact(additional);
+    }
+
+    pub(crate) fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.hashes.try_reserve(additional)?;
+        self.links.try_reserve(additional)
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.hashes.shrink_to_fit();
+        self.links.shrink_to_fit();
+        for chain in &mut self.buckets {
+            if let Chain::Sorted(sorted) = chain {
+                sorted.shrink_to_fit();
+            }
+        }
+    }
+}
+
+// lists a chain's entries ordered by key hash, then by key
+fn sort_chain<O>(chain: &Chain, hashes: &[u64], links: &[usize], key_order: &O) -> Chain
+where
+    O: Fn(usize, usize) -> Option<Ordering>,
+{
+    let mut sorted: Vec<usize> = chain.indices(links).collect();
+    sorted.sort_by(|&a, &b| {
+        hashes[a]
+            .cmp(&hashes[b])
+            .then_with(|| key_order(a, b).unwrap_or(Ordering::Equal))
+    });
+    Chain::Sorted(sorted)
+}
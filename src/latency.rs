@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// each power of two range of nanoseconds is split into this many linear sub-buckets, which keeps
+// recorded values within ~6% of their true value (the same log-linear layout HDR histograms use)
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const NUMBER_OF_BUCKETS: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKETS;
+
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+    let exponent = 63 - nanos.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let mantissa = (nanos >> shift) as usize;
+    (shift as usize + 1) * SUB_BUCKETS + mantissa - SUB_BUCKETS
+}
+
+// the largest value that would have been recorded into `index`
+fn highest_equivalent_value(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let mantissa = (index % SUB_BUCKETS + SUB_BUCKETS) as u64;
+    (mantissa << shift) + ((1u64 << shift) - 1)
+}
+
+/// A fixed-size log-linear histogram of operation latencies.
+///
+/// Recording only touches atomics, so it works through `&self` and keeps tables `Sync`. The ~8 KB of
+/// counts is only allocated once the first latency is recorded, so tables that never run an operation
+/// don't pay for it.
+pub struct LatencyHistogram {
+    counts: OnceLock<Box<[AtomicU64]>>,
+    max: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub(crate) fn new() -> LatencyHistogram {
+        LatencyHistogram {
+            counts: OnceLock::new(),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record(&self, elapsed: Duration) {
+        let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        let counts = self
+            .counts
+            .get_or_init(|| (0..NUMBER_OF_BUCKETS).map(|_| AtomicU64::new(0)).collect());
+        counts[bucket_index(nanos)].fetch_add(1, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    fn counts(&self) -> &[AtomicU64] {
+        self.counts.get().map_or(&[], |counts| counts)
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts()
+            .iter()
+            .map(|c| c.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// The latency at or below which `percentile` percent of recorded operations completed.
+    /// Returns zero when nothing has been recorded.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let count = self.count();
+        if count == 0 {
+            return Duration::from_nanos(0);
+        }
+
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, c) in self.counts().iter().enumerate() {
+            seen += c.load(Ordering::Relaxed);
+            if seen >= rank {
+                let nanos = highest_equivalent_value(index).min(self.max.load(Ordering::Relaxed));
+                return Duration::from_nanos(nanos);
+            }
+        }
+        self.max()
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max.load(Ordering::Relaxed))
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count(),
+            p50: self.percentile(50.0),
+            p90: self.percentile(90.0),
+            p99: self.percentile(99.0),
+            p999: self.percentile(99.9),
+            max: self.max(),
+        }
+    }
+
+    pub fn reset(&self) {
+        for c in self.counts() {
+            c.store(0, Ordering::Relaxed);
+        }
+        self.max.store(0, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+    pub max: Duration,
+}
+
+/// Latency histograms for a table's core operations.
+///
+/// Only `get`, `insert` and `remove` are timed. Other lookups, the entry API and bulk operations such
+/// as `remove_batch`, `retain`, `extract_if` and `drain` aren't recorded.
+pub struct OperationLatencies {
+    pub get: LatencyHistogram,
+    pub insert: LatencyHistogram,
+    pub remove: LatencyHistogram,
+}
+
+impl OperationLatencies {
+    pub(crate) fn new() -> OperationLatencies {
+        OperationLatencies {
+            get: LatencyHistogram::new(),
+            insert: LatencyHistogram::new(),
+            remove: LatencyHistogram::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{bucket_index, highest_equivalent_value, LatencyHistogram, NUMBER_OF_BUCKETS};
+    use crate::HashTable;
+
+    #[test]
+    fn test_bucket_boundaries() {
+        let mut previous = 0;
+        for nanos in (0..100_000).chain([u64::MAX / 2, u64::MAX]) {
+            let index = bucket_index(nanos);
+            assert!(index < NUMBER_OF_BUCKETS);
+            assert!(index >= previous);
+            assert!(highest_equivalent_value(index) >= nanos);
+            previous = index;
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let histogram = LatencyHistogram::new();
+        assert_eq!(histogram.percentile(99.0), Duration::from_nanos(0));
+        // nothing is allocated until something is recorded
+        assert!(histogram.counts.get().is_none());
+
+        for nanos in 1..=1000 {
+            histogram.record(Duration::from_nanos(nanos));
+        }
+
+        let summary = histogram.summary();
+        assert_eq!(summary.count, 1000);
+        assert_eq!(summary.max, Duration::from_nanos(1000));
+        // values are only precise to their sub-bucket
+        let p50 = summary.p50.as_nanos();
+        assert!((500..=532).contains(&p50), "p50 was {}", p50);
+        let p99 = summary.p99.as_nanos();
+        assert!((990..=1000).contains(&p99), "p99 was {}", p99);
+
+        histogram.reset();
+        assert_eq!(histogram.count(), 0);
+    }
+
+    #[test]
+    fn test_table_records_operations() {
+        let mut hash_table = HashTable::new();
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("theo", 0);
+        hash_table.get(&"gedalia");
+        hash_table.remove(&"theo");
+        hash_table.remove_batch(vec!["gedalia"]);
+        hash_table.retain(|_, _| false);
+
+        let latencies = hash_table.latencies();
+        assert_eq!(latencies.insert.count(), 2);
+        assert_eq!(latencies.get.count(), 1);
+        assert_eq!(latencies.remove.count(), 1);
+    }
+}
@@ -0,0 +1,157 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::{DefaultSimpleHasher, HashTable, SimpleHasher};
+
+/// A stack of hash tables for scoped lookups, like variables in nested scopes or configuration
+/// overriding defaults.
+///
+/// Writes go to the top layer and reads fall through from the top layer down to the base until the
+/// key is found. The base layer is always there.
+pub struct LayeredHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    // the base layer first, never empty
+    layers: Vec<HashTable<K, V, H>>,
+}
+
+impl<K, V> Default for LayeredHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        LayeredHashTable {
+            layers: vec![Default::default()],
+        }
+    }
+}
+
+impl<K, V> LayeredHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> LayeredHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> LayeredHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K> + Clone,
+{
+    pub fn with_hasher(hasher: H) -> LayeredHashTable<K, V, H> {
+        LayeredHashTable {
+            layers: vec![HashTable::with_hasher(hasher)],
+        }
+    }
+
+    /// Starts a new, empty top layer.
+    pub fn push_layer(&mut self) {
+        let hasher = self.top().hasher.clone();
+        self.layers.push(HashTable::with_hasher(hasher));
+    }
+
+    /// Removes and returns the top layer, or `None` when only the base layer is left.
+    pub fn pop_layer(&mut self) -> Option<HashTable<K, V, H>> {
+        if self.layers.len() == 1 {
+            return None;
+        }
+        self.layers.pop()
+    }
+
+    /// The number of layers, counting the base layer.
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Inserts into the top layer, shadowing the key in any layer below. Only returns a value the top
+    /// layer already had.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.top_mut().insert(k, v)
+    }
+
+    /// The value from the highest layer that has `k`.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.layers.iter().rev().find_map(|layer| layer.get(k))
+    }
+
+    /// Like `get`, so this may change a value in a layer below the top one.
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.layers
+            .iter_mut()
+            .rev()
+            .find_map(|layer| layer.get_mut(k))
+    }
+
+    /// Removes `k` from the top layer only, which uncovers its value in the layers below if they have one.
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        self.top_mut().remove(k)
+    }
+
+    /// Collapses every layer into a single table, keeping each key's value from the highest layer that has it.
+    pub fn flatten(self) -> HashTable<K, V, H> {
+        let mut layers = self.layers.into_iter();
+        let mut flattened = layers.next().expect("there is always a base layer");
+        for layer in layers {
+            for (k, v) in layer {
+                flattened.insert(k, v);
+            }
+        }
+        flattened
+    }
+
+    fn top(&self) -> &HashTable<K, V, H> {
+        self.layers.last().expect("there is always a base layer")
+    }
+
+    fn top_mut(&mut self) -> &mut HashTable<K, V, H> {
+        self.layers
+            .last_mut()
+            .expect("there is always a base layer")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LayeredHashTable;
+
+    #[test]
+    fn test_scoped_lookups() {
+        let mut scopes = LayeredHashTable::new();
+        scopes.insert("x", 1);
+        scopes.insert("y", 2);
+        assert_eq!(scopes.pop_layer().map(|_| ()), None);
+
+        scopes.push_layer();
+        scopes.insert("x", 10);
+        *scopes.get_mut(&"y").unwrap() += 20;
+        scopes.insert("z", 30);
+        assert_eq!(scopes.depth(), 2);
+        assert_eq!(scopes.get(&"x"), Some(&10));
+        assert_eq!(scopes.get(&"y"), Some(&22));
+
+        // removing from the top uncovers the base layer's value
+        assert_eq!(scopes.remove(&"x"), Some(10));
+        assert_eq!(scopes.get(&"x"), Some(&1));
+        scopes.insert("x", 100);
+
+        let popped = scopes.pop_layer().unwrap();
+        assert_eq!(popped.get(&"z"), Some(&30));
+        assert_eq!(scopes.get(&"x"), Some(&1));
+        assert_eq!(scopes.get(&"z"), None);
+
+        scopes.push_layer();
+        scopes.insert("x", 1000);
+        scopes.push_layer();
+        scopes.insert("w", 0);
+        let flattened = scopes.flatten();
+        assert_eq!(flattened.get(&"x"), Some(&1000));
+        assert_eq!(flattened.get(&"y"), Some(&22));
+        assert_eq!(flattened.get(&"w"), Some(&0));
+        assert_eq!(flattened.get(&"z"), None);
+    }
+}
@@ -1,26 +1,193 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::vec::IntoIter;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::TryReserveError;
+use alloc::vec;
+use alloc::vec::{IntoIter, Vec};
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::hash::Hash;
+
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use std::hash::BuildHasher;
+#[cfg(feature = "latency-histograms")]
+use std::time::Instant;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::adaptive::AdaptiveLoadFactor;
+use crate::hash_index::HashIndex;
+
+#[cfg(feature = "std")]
+mod accessed;
+mod adaptive;
+mod arc;
+mod boxed;
+mod build_hasher;
+mod builder;
+mod columnar;
+#[cfg(feature = "std")]
+mod counter;
+mod flagged;
+mod float_key;
+mod fnv;
+mod hash_index;
+#[cfg(feature = "latency-histograms")]
+mod latency;
+mod layered;
+mod lru;
+mod multimap;
+#[cfg(feature = "std")]
+mod normalize;
+mod ord_keys;
+mod ordered;
+mod prefix;
+#[cfg(feature = "std")]
+mod rate_limiter;
+#[cfg(feature = "serde")]
+mod serialize;
+mod set;
+#[cfg(feature = "std")]
+mod sharded;
+mod sorted_index;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "std")]
+mod sync;
+#[cfg(feature = "std")]
+mod windowed;
+
+#[cfg(feature = "std")]
+pub use accessed::AccessTrackedHashTable;
+pub use arc::ArcHashTable;
+pub use boxed::BoxedHashTable;
+pub use build_hasher::BuildHasherAdapter;
+pub use builder::HashTableBuilder;
+pub use columnar::ColumnarHashTable;
+#[cfg(feature = "std")]
+pub use counter::AtomicCounterTable;
+pub use flagged::FlaggedHashTable;
+pub use float_key::{Float, FloatKey};
+pub use fnv::FnvSimpleHasher;
+#[cfg(feature = "latency-histograms")]
+pub use latency::{LatencyHistogram, LatencySummary, OperationLatencies};
+pub use layered::LayeredHashTable;
+pub use lru::{LruHashTable, LruIter};
+pub use multimap::{HashMultiTable, MultiEntry, OccupiedMultiEntry, VacantMultiEntry};
+#[cfg(feature = "std")]
+pub use normalize::{AsciiCaseInsensitive, KeyNormalize, NormalizedHasher};
+pub use ord_keys::OrdKeyHasher;
+pub use ordered::OrderedHashTable;
+pub use prefix::PrefixHashTable;
+#[cfg(feature = "std")]
+pub use rate_limiter::{Decision, RateLimiterTable};
+pub use set::{
+    Difference, HashTableSet, Intersection, SetIntoIter, SetIter, SymmetricDifference, Union,
+};
+#[cfg(feature = "std")]
+pub use sharded::{ReadCache, ShardedHashTable};
+pub use sorted_index::SortedIndexHashTable;
+#[cfg(feature = "std")]
+pub use windowed::WindowedCounterTable;
+
+// by default the table grows once entries per bucket would exceed this
+const MAX_LOAD_FACTOR: f64 = 0.75;
+
+// the most entries a table grows for up front on the say-so of its input, which could claim any length
+#[cfg(any(feature = "futures", feature = "serde"))]
+const MAX_PREALLOCATED_ENTRIES: usize = 4096;
+
+// the fewest buckets that hold `entries` within `load_factor`
+fn buckets_for(entries: usize, load_factor: f64) -> usize {
+    // rounds up by hand since `f64::ceil` needs std
+    let exact = entries as f64 / load_factor;
+    let truncated = exact as usize;
+    let buckets = if (truncated as f64) < exact {
+        truncated + 1
+    } else {
+        truncated
+    };
+    buckets.max(1)
+}
 
+/// Hashes a table's keys.
+///
+/// A hasher that also implements `SimpleHasher<Q>` for a borrowed form `Q` of its keys lets tables
+/// look keys up by `&Q`. It must hash and compare a key and its borrowed form identically, as `Hash`
+/// and `Eq` must for `Borrow`.
 pub trait SimpleHasher<K>
 where
-    K: Hash,
+    K: Hash + ?Sized,
 {
     fn hash(&self, t: &K) -> u64;
+
+    /// Whether two keys address the same entry. Hashers that normalize keys before hashing them
+    /// must compare them the same way.
+    fn key_eq(&self, a: &K, b: &K) -> bool
+    where
+        K: PartialEq,
+    {
+        a == b
+    }
+
+    /// How two keys order, for hashers of keys that have an order. Tables binary search long runs of
+    /// keys sharing a hash by it, and compare them one at a time when it's `None`. Must agree with
+    /// `key_eq`, and be `Some` for every pair of keys or for none.
+    fn key_cmp(&self, _a: &K, _b: &K) -> Option<Ordering> {
+        None
+    }
 }
 
-pub struct DefaultSimpleHasher;
+/// The hasher tables use unless given another: SipHash, as std's `HashMap` uses, keyed with a random
+/// seed per hasher so nobody can work out colliding keys ahead of time. Clones share their seed.
+///
+/// Without the `std` feature there is no source of randomness, so this falls back to the same unseeded
+/// FNV-1a as `FnvSimpleHasher`.
+#[derive(Clone, Debug)]
+pub struct DefaultSimpleHasher {
+    #[cfg(feature = "std")]
+    state: RandomState,
+}
 impl DefaultSimpleHasher {
     fn new() -> Self {
-        DefaultSimpleHasher
+        DefaultSimpleHasher {
+            #[cfg(feature = "std")]
+            state: RandomState::new(),
+        }
+    }
+}
+impl Default for DefaultSimpleHasher {
+    fn default() -> Self {
+        DefaultSimpleHasher::new()
     }
 }
-impl<K: Hash> SimpleHasher<K> for DefaultSimpleHasher {
+impl<K: Hash + ?Sized> SimpleHasher<K> for DefaultSimpleHasher {
+    #[cfg(feature = "std")]
     fn hash(&self, t: &K) -> u64 {
-        let mut s = DefaultHasher::new();
-        t.hash(&mut s);
-        s.finish()
+        self.state.hash_one(t)
     }
+
+    #[cfg(not(feature = "std"))]
+    fn hash(&self, t: &K) -> u64 {
+        FnvSimpleHasher::new().hash(t)
+    }
+}
+
+// orders the keys of two entries, as `HashIndex` needs
+fn key_order<'a, K, V, H>(
+    entries: &'a [(K, V)],
+    hasher: &'a H,
+) -> impl Fn(usize, usize) -> Option<Ordering> + 'a
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    move |a, b| hasher.key_cmp(&entries[a].0, &entries[b].0)
 }
 
 pub struct HashTable<K, V, H = DefaultSimpleHasher>
@@ -28,9 +195,19 @@ where
     H: SimpleHasher<K>,
     K: Hash,
 {
-    buckets: Vec<Vec<(K, V)>>,
-    total_entries: usize,
+    // every entry, packed together in no particular order
+    entries: Vec<(K, V)>,
+    // where each entry sits, by its key's hash
+    index: HashIndex,
     hasher: H,
+    max_load_factor: f64,
+    adaptive_load_factor: Option<AdaptiveLoadFactor>,
+    max_chain_length: Option<usize>,
+    chain_length_resizes: usize,
+    #[cfg(feature = "latency-histograms")]
+    latencies: OperationLatencies,
+    #[cfg(feature = "probe-length-assertions")]
+    max_probe_length: Option<usize>,
 }
 
 impl<K, V> Default for HashTable<K, V, DefaultSimpleHasher>
@@ -39,159 +216,966 @@ where
 {
     fn default() -> Self {
         let default_number_of_starting_buckets = 10;
-        let mut buckets: Vec<Vec<(K, V)>> = vec![];
-        for _ in 0..default_number_of_starting_buckets {
-            buckets.push(vec![]);
-        }
-        let hasher = DefaultSimpleHasher::new();
-
-        HashTable {
-            buckets,
-            total_entries: 0,
-            hasher,
-        }
+        HashTable::from_parts(
+            default_number_of_starting_buckets,
+            DefaultSimpleHasher::new(),
+        )
     }
 }
 
 impl<K, V> HashTable<K, V, DefaultSimpleHasher>
 where
-    K: std::hash::Hash + PartialEq,
+    K: core::hash::Hash + PartialEq,
 {
     pub fn new() -> HashTable<K, V, DefaultSimpleHasher> {
         Default::default()
     }
 
+    /// A table that holds `capacity` entries without resizing.
     pub fn with_capacity(capacity: usize) -> HashTable<K, V, DefaultSimpleHasher> {
-        let mut buckets: Vec<Vec<(K, V)>> = vec![];
-        for _ in 0..capacity {
-            buckets.push(vec![]);
+        HashTable::from_parts(
+            buckets_for(capacity, MAX_LOAD_FACTOR),
+            DefaultSimpleHasher::new(),
+        )
+    }
+
+    /// Starts configuring a table, for when the options needed go beyond a single constructor.
+    pub fn builder() -> HashTableBuilder<K, V, DefaultSimpleHasher> {
+        HashTableBuilder::new()
+    }
+
+    /// Builds a table mapping each key to `f(&key)`, sized once up front from the iterator's size hint.
+    /// A repeated key keeps its first value and `f` isn't called for it again.
+    pub fn from_keys_with<I, F>(keys: I, mut f: F) -> HashTable<K, V, DefaultSimpleHasher>
+    where
+        I: IntoIterator<Item = K>,
+        F: FnMut(&K) -> V,
+    {
+        let keys = keys.into_iter();
+        let mut hash_table = HashTable::new();
+        hash_table.reserve_exact(keys.size_hint().0);
+        for k in keys {
+            let hash = hash_table.hasher.hash(&k);
+            if hash_table.find(hash, &k).is_none() {
+                let v = f(&k);
+                hash_table._insert(k, v, hash);
+            }
         }
+        hash_table
+    }
 
-        HashTable {
-            buckets,
-            total_entries: 0,
-            hasher: DefaultSimpleHasher::new(),
+    /// Builds a table from `pairs`, failing on the first key that appears twice rather than keeping its
+    /// last value.
+    pub fn try_from_iter<I>(
+        pairs: I,
+    ) -> Result<HashTable<K, V, DefaultSimpleHasher>, DuplicateKeyError<K>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let pairs = pairs.into_iter();
+        let mut hash_table = HashTable::new();
+        hash_table.reserve_exact(pairs.size_hint().0);
+        for (k, v) in pairs {
+            let hash = hash_table.hasher.hash(&k);
+            if hash_table.find(hash, &k).is_some() {
+                return Err(DuplicateKeyError { key: k });
+            }
+            hash_table._insert(k, v, hash);
+        }
+        Ok(hash_table)
+    }
+
+    /// Like `from_keys_with`, but trusts the caller that no key repeats and skips looking for
+    /// duplicates. A repeated key is not unsafe, but leaves the table with more than one entry for it.
+    pub fn from_unique_keys_with<I, F>(keys: I, mut f: F) -> HashTable<K, V, DefaultSimpleHasher>
+    where
+        I: IntoIterator<Item = K>,
+        F: FnMut(&K) -> V,
+    {
+        let keys = keys.into_iter();
+        let mut hash_table = HashTable::new();
+        hash_table.reserve_exact(keys.size_hint().0);
+        for k in keys {
+            let hash = hash_table.hasher.hash(&k);
+            let v = f(&k);
+            hash_table._insert(k, v, hash);
         }
+        hash_table
     }
 }
 
-impl<K, V, H> HashTable<K, V, H>
+impl<'a, K, V> HashTable<&'a K, V, DefaultSimpleHasher>
 where
-    K: std::hash::Hash + PartialEq,
-    H: SimpleHasher<K>,
+    K: Hash + PartialEq + ?Sized,
 {
-    pub fn with_hasher(hasher: H) -> HashTable<K, V, H> {
-        let mut buckets: Vec<Vec<(K, V)>> = vec![];
-        for _ in 0..10 {
-            buckets.push(vec![]);
+    /// Builds a table whose keys borrow from data that outlives it, such as `&str` slices of a parsed
+    /// buffer, so no key is copied. Sized once up front from the iterator's size hint; a repeated key
+    /// keeps its last value.
+    pub fn from_pairs_borrowed<I>(pairs: I) -> HashTable<&'a K, V, DefaultSimpleHasher>
+    where
+        I: IntoIterator<Item = (&'a K, V)>,
+    {
+        let pairs = pairs.into_iter();
+        let mut hash_table = HashTable::new();
+        hash_table.reserve_exact(pairs.size_hint().0);
+        for (k, v) in pairs {
+            hash_table.insert(k, v);
         }
+        hash_table
+    }
+}
 
+impl<K, V, H> HashTable<K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    fn from_parts(number_of_buckets: usize, hasher: H) -> HashTable<K, V, H> {
         HashTable {
-            buckets,
-            total_entries: 0,
+            entries: vec![],
+            index: HashIndex::new(number_of_buckets),
             hasher,
+            max_load_factor: MAX_LOAD_FACTOR,
+            adaptive_load_factor: None,
+            max_chain_length: None,
+            chain_length_resizes: 0,
+            #[cfg(feature = "latency-histograms")]
+            latencies: OperationLatencies::new(),
+            #[cfg(feature = "probe-length-assertions")]
+            max_probe_length: None,
+        }
+    }
+
+    /// Rebuilds a table from the parts `into_raw_parts` returns, without checking anything. Every key is
+    /// hashed once more, as the table keeps its keys' hashes alongside them.
+    /// Options such as the load factor and maximum chain length are back to their defaults.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that, with `hasher` hashing keys:
+    /// - `buckets` is not empty
+    /// - every entry sits in bucket `hash % buckets.len()`
+    /// - no two entries hold keys that are equal
+    /// - every bucket longer than 8 entries is ordered by hash, then by `key_cmp` where it's `Some`
+    /// - `total_entries` is the number of entries across all buckets
+    ///
+    /// A table built from parts that break these may lose, duplicate or misplace entries, panic, or
+    /// misbehave in any other way the table's own code has to assume can't happen.
+    pub unsafe fn from_raw_parts(
+        buckets: Vec<Vec<(K, V)>>,
+        total_entries: usize,
+        hasher: H,
+    ) -> HashTable<K, V, H> {
+        let mut hash_table = HashTable::from_parts(buckets.len(), hasher);
+        hash_table.entries.reserve(total_entries);
+        hash_table.index.reserve(total_entries);
+        for entry in buckets.into_iter().flatten() {
+            let hash = hash_table.hasher.hash(&entry.0);
+            hash_table.entries.push(entry);
+            let HashTable {
+                entries,
+                index,
+                hasher,
+                ..
+            } = &mut hash_table;
+            index.push(hash, key_order(entries, hasher));
         }
+        hash_table
+    }
+}
+
+impl<K, V, H> HashTable<K, V, H>
+where
+    K: core::hash::Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> HashTable<K, V, H> {
+        HashTable::from_parts(10, hasher)
     }
 
-    pub fn insert(&mut self, mut k: K, v: V) -> Option<V> {
+    /// Sets the number of entries per bucket the table grows past, 0.75 by default. Panics unless
+    /// `load_factor` is positive and finite.
+    pub fn with_load_factor(mut self, load_factor: f64) -> HashTable<K, V, H> {
+        assert!(
+            load_factor > 0.0 && load_factor.is_finite(),
+            "a load factor must be positive and finite"
+        );
+        self.max_load_factor = match &self.adaptive_load_factor {
+            Some(adaptive) => adaptive.clamp(load_factor),
+            None => load_factor,
+        };
+        self
+    }
+
+    /// Grows the table whenever an insert would push a single chain past `max_chain_length`, regardless of
+    /// the overall load factor. Meant for tables keyed by untrusted input where collisions can be forced.
+    pub fn with_max_chain_length(mut self, max_chain_length: usize) -> HashTable<K, V, H> {
+        self.max_chain_length = Some(max_chain_length);
+        self
+    }
+
+    /// Panics whenever a lookup has to compare against more than `max_probe_length` keys, reporting the
+    /// key's hash and the state of the buckets. Meant for catching a badly distributed `SimpleHasher`
+    /// during development.
+    #[cfg(feature = "probe-length-assertions")]
+    pub fn with_max_probe_length(mut self, max_probe_length: usize) -> HashTable<K, V, H> {
+        self.max_probe_length = Some(max_probe_length);
+        self
+    }
+
+    /// The number of entries per bucket the table grows past. Only changes over time on tables built
+    /// with `HashTableBuilder::adaptive_load_factor`.
+    pub fn load_factor(&self) -> f64 {
+        self.max_load_factor
+    }
+
+    /// How many times the table has grown because of `max_chain_length` rather than its load factor.
+    pub fn chain_length_resizes(&self) -> usize {
+        self.chain_length_resizes
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        #[cfg(feature = "latency-histograms")]
+        let start = Instant::now();
+
         // check if this key is being used
         let hash = self.hasher.hash(&k);
-        let bucket_index = hash as usize % self.buckets.len();
-        let mut to_remove = None;
-        for (pos, (ek, _)) in self.buckets[bucket_index].iter().enumerate() {
-            if ek == &mut k {
-                // we are using a value for this key that needs to be replaced
-                to_remove = Some(pos);
-                break;
-            }
-        }
-        match to_remove {
+        let old_v = match self.find(hash, &k) {
             Some(index) => {
-                let (_, ov) = std::mem::replace(&mut self.buckets[bucket_index][index], (k, v));
+                let (_, ov) = core::mem::replace(&mut self.entries[index], (k, v));
                 Some(ov)
             }
             None => {
                 self._insert(k, v, hash);
                 None
             }
-        }
+        };
+
+        #[cfg(feature = "latency-histograms")]
+        self.latencies.insert.record(start.elapsed());
+        old_v
     }
 
-    fn _insert(&mut self, k: K, v: V, hash: u64) -> &mut V {
-        // first check if we need to prepare for capacity changes
-        let new_load_factor = (self.total_entries + 1) as f64 / self.buckets.len() as f64;
-        if new_load_factor > 0.75 {
-            let mut new_buckets: Vec<Vec<(K, V)>> = vec![];
-            let extended_number_of_buckets = self.buckets.len() * 2;
-            for _ in 0..extended_number_of_buckets {
-                new_buckets.push(vec![]);
+    /// Inserts like `insert` and also hands back a mutable reference to the stored value.
+    pub fn insert_then_get(&mut self, k: K, v: V) -> (&mut V, Option<V>) {
+        let hash = self.hasher.hash(&k);
+        let (index, old_v) = match self.find(hash, &k) {
+            Some(index) => {
+                let (_, ev) = &mut self.entries[index];
+                (index, Some(core::mem::replace(ev, v)))
             }
+            None => (self._insert(k, v, hash), None),
+        };
+        let (_, v) = &mut self.entries[index];
+        (v, old_v)
+    }
 
-            for mut bucket in self.buckets.drain(..) {
-                for (ek, ev) in bucket.drain(..) {
-                    let hash = self.hasher.hash(&ek);
-                    let new_bucket_index = hash as usize % new_buckets.len();
-                    new_buckets[new_bucket_index].push((ek, ev));
+    /// Inserts every pair from `iter`. When a key is already present, `merge` is handed the existing
+    /// value and the incoming one to combine in place instead of the incoming value replacing it.
+    pub fn extend_with<I, F>(&mut self, iter: I, mut merge: F)
+    where
+        I: IntoIterator<Item = (K, V)>,
+        F: FnMut(&K, &mut V, V),
+    {
+        let iter = iter.into_iter();
+        // incoming keys may well overlap with existing ones, so only plan for half of them being new
+        let hint = iter.size_hint().0;
+        if self.entries.is_empty() {
+            self.reserve_exact(hint);
+        } else {
+            self.reserve_exact(hint.div_ceil(2));
+        }
+        for (k, v) in iter {
+            let hash = self.hasher.hash(&k);
+            match self.find(hash, &k) {
+                Some(index) => {
+                    let (ek, ev) = &mut self.entries[index];
+                    merge(ek, ev, v);
+                }
+                None => {
+                    self._insert(k, v, hash);
                 }
             }
+        }
+    }
+
+    /// Like `insert`, but an existing entry has its key swapped out too and the old pair is returned.
+    pub fn replace(&mut self, k: K, v: V) -> Option<(K, V)> {
+        let hash = self.hasher.hash(&k);
+        match self.find(hash, &k) {
+            Some(index) => Some(core::mem::replace(&mut self.entries[index], (k, v))),
+            None => {
+                self._insert(k, v, hash);
+                None
+            }
+        }
+    }
+
+    fn _insert(&mut self, k: K, v: V, hash: u64) -> usize {
+        // first check if we need to prepare for capacity changes
+        if let Some(adaptive) = &self.adaptive_load_factor {
+            self.max_load_factor = adaptive.adjust(self.max_load_factor);
+        }
+        let number_of_buckets = self.index.number_of_buckets();
+        let new_load_factor = (self.entries.len() + 1) as f64 / number_of_buckets as f64;
+        if new_load_factor > self.max_load_factor {
+            self.resize(number_of_buckets * 2);
+        } else if self.chain_would_overflow(hash) {
+            self.chain_length_resizes += 1;
+            self.resize(number_of_buckets * 2);
+        }
+
+        // then add the new item and hand back where it landed so callers can address it without probing again
+        let index = self.entries.len();
+        self.entries.push((k, v));
+        self.index
+            .push(hash, key_order(&self.entries, &self.hasher));
+        index
+    }
+
+    /// Makes room for `additional` more entries without resizing. Grows to at least twice as many
+    /// buckets, so a run of small reserves doesn't rehash on every call.
+    pub fn reserve(&mut self, additional: usize) {
+        let entries = self
+            .entries
+            .len()
+            .checked_add(additional)
+            .expect("capacity overflow");
+        let number_of_buckets = buckets_for(entries, self.max_load_factor);
+        let current = self.index.number_of_buckets();
+        if number_of_buckets > current {
+            self.resize(number_of_buckets.max(current * 2));
+        }
+        self.entries.reserve(additional);
+        self.index.reserve(additional);
+    }
+
+    /// Like `reserve`, but returns an error instead of aborting if the bucket array or the room for
+    /// entries can't be allocated.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.entries.try_reserve(additional)?;
+        self.index.try_reserve(additional)?;
+
+        // an overflowing count asks for more buckets than can exist, which the allocation reports
+        let entries = self.entries.len().saturating_add(additional);
+        let number_of_buckets = buckets_for(entries, self.max_load_factor);
+        let current = self.index.number_of_buckets();
+        if number_of_buckets <= current {
+            return Ok(());
+        }
+
+        self.index.try_resize(
+            number_of_buckets.max(current * 2),
+            key_order(&self.entries, &self.hasher),
+        )
+    }
+
+    /// Grows to the fewest buckets that hold `additional` more entries without resizing, rather than
+    /// doubling. Does nothing if the table is already big enough.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        let entries = self
+            .entries
+            .len()
+            .checked_add(additional)
+            .expect("capacity overflow");
+        let number_of_buckets = buckets_for(entries, self.max_load_factor);
+        if number_of_buckets > self.index.number_of_buckets() {
+            self.resize(number_of_buckets);
+        }
+        self.entries.reserve_exact(additional);
+        self.index.reserve_exact(additional);
+    }
+
+    /// Shrinks to the fewest buckets that hold the current entries, and frees any room beyond them.
+    /// Useful after removing most of a table.
+    pub fn shrink_to_fit(&mut self) {
+        let number_of_buckets = buckets_for(self.entries.len(), self.max_load_factor);
+        if number_of_buckets < self.index.number_of_buckets() {
+            self.resize(number_of_buckets);
+        }
+        self.entries.shrink_to_fit();
+        self.index.shrink_to_fit();
+    }
+
+    fn resize(&mut self, number_of_buckets: usize) {
+        self.index
+            .resize(number_of_buckets, key_order(&self.entries, &self.hasher));
+    }
+
+    /// Like `reserve_exact`, but works out every entry's new bucket across rayon's thread pool. Keys are
+    /// never rehashed when the table grows, so this only pays off for very large tables.
+    #[cfg(feature = "rayon")]
+    pub fn par_reserve_exact(&mut self, additional: usize) {
+        let entries = self
+            .entries
+            .len()
+            .checked_add(additional)
+            .expect("capacity overflow");
+        let number_of_buckets = buckets_for(entries, self.max_load_factor);
+        if number_of_buckets > self.index.number_of_buckets() {
+            self.par_resize(number_of_buckets);
+        }
+        self.entries.reserve_exact(additional);
+        self.index.reserve_exact(additional);
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_resize(&mut self, number_of_buckets: usize) {
+        // linking chains has to happen in order, so only the bucket arithmetic is spread across threads
+        let bucket_indices: Vec<usize> = self
+            .index
+            .hashes()
+            .par_iter()
+            .map(|&hash| hash as usize % number_of_buckets)
+            .collect();
+        self.index.resize_into(
+            number_of_buckets,
+            bucket_indices,
+            key_order(&self.entries, &self.hasher),
+        );
+    }
+
+    fn chain_would_overflow(&self, hash: u64) -> bool {
+        let max_chain_length = match self.max_chain_length {
+            Some(max_chain_length) => max_chain_length,
+            None => return false,
+        };
+        self.index.chain_would_overflow(hash, max_chain_length)
+    }
 
-            self.buckets = new_buckets;
+    fn find<Q>(&self, hash: u64, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        let (found, probe_length) = self.probe(hash, k);
+        if let Some(adaptive) = &self.adaptive_load_factor {
+            adaptive.record(probe_length, found.is_some());
         }
+        #[cfg(feature = "probe-length-assertions")]
+        self.check_probe_length(hash, probe_length);
+        found
+    }
+
+    // also returns how many keys were compared against to get the answer
+    fn probe<Q>(&self, hash: u64, k: &Q) -> (Option<usize>, usize)
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        let (entries, hasher) = (&self.entries, &self.hasher);
+        self.index.find(
+            hash,
+            |index| hasher.key_eq(entries[index].0.borrow(), k),
+            |index| hasher.key_cmp(entries[index].0.borrow(), k),
+        )
+    }
+
+    #[cfg(feature = "probe-length-assertions")]
+    fn check_probe_length(&self, hash: u64, probe_length: usize) {
+        let max_probe_length = match self.max_probe_length {
+            Some(max_probe_length) if probe_length > max_probe_length => max_probe_length,
+            _ => return,
+        };
+
+        let bucket_index = self.index.bucket_of(hash);
+        let chain_length = self.index.chain_lengths().nth(bucket_index).unwrap_or(0);
+        let longest_chain = self.index.chain_lengths().max().unwrap_or(0);
+        let empty_buckets = self.index.chain_lengths().filter(|&len| len == 0).count();
+        panic!(
+            "probe for hash {:#018x} compared {} keys, over the limit of {}: bucket {} of {} holds {} entries, \
+             the longest chain holds {}, {} buckets are empty and the table holds {} entries",
+            hash,
+            probe_length,
+            max_probe_length,
+            bucket_index,
+            self.index.number_of_buckets(),
+            chain_length,
+            longest_chain,
+            empty_buckets,
+            self.entries.len()
+        );
+    }
+
+    /// Looks `k` up by any borrowed form of the table's key type, such as `&str` for `String` keys.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        #[cfg(feature = "latency-histograms")]
+        let start = Instant::now();
+
+        let hash = self.hasher.hash(k);
+        let v = self.find(hash, k).map(|index| &self.entries[index].1);
 
-        // then add the new item (give up ownership of input v late so we can easily access the value for returning)
-        let bucket_index = hash as usize % self.buckets.len();
-        self.buckets[bucket_index].push((k, v));
-        self.total_entries += 1;
-        let len = self.buckets[bucket_index].len();
-        let (_, v) = &mut self.buckets[bucket_index][len - 1];
+        #[cfg(feature = "latency-histograms")]
+        self.latencies.get.record(start.elapsed());
         v
     }
 
-    pub fn get(&self, k: &K) -> Option<&V> {
+    /// Returns a copy of the value, so the caller doesn't hold a borrow of the table. For values that are
+    /// expensive to clone see `ArcHashTable`.
+    pub fn get_cloned<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+        V: Clone,
+    {
+        self.get(k).cloned()
+    }
+
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
         let hash = self.hasher.hash(k);
-        let bucket_index = hash as usize % self.buckets.len();
-        for (ek, v) in &self.buckets[bucket_index] {
-            if ek == k {
-                return Some(v);
-            }
-        }
-        None
+        let index = self.find(hash, k)?;
+        let (_, v) = &mut self.entries[index];
+        Some(v)
     }
 
-    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        #[cfg(feature = "latency-histograms")]
+        let start = Instant::now();
+
         let hash = self.hasher.hash(k);
-        let bucket_index = hash as usize % self.buckets.len();
-        for (ek, v) in &mut self.buckets[bucket_index] {
-            if ek == k {
-                return Some(v);
-            }
+        let v = self.find(hash, k).map(|index| self.remove_at(index).1);
+
+        #[cfg(feature = "latency-histograms")]
+        self.latencies.remove.record(start.elapsed());
+        v
+    }
+
+    /// Removes every key in `keys`, returning each one's value (or `None`) in the order the keys were given.
+    /// Every key is hashed up front and removals then go in bucket order, which keeps lookups in the
+    /// bucket array moving forwards; the entries themselves still sit wherever the table put them.
+    pub fn remove_batch<I>(&mut self, keys: I) -> Vec<Option<V>>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let number_of_buckets = self.index.number_of_buckets();
+        let mut removals: Vec<(usize, usize, u64, K)> = keys
+            .into_iter()
+            .enumerate()
+            .map(|(position, k)| {
+                let hash = self.hasher.hash(&k);
+                (hash as usize % number_of_buckets, position, hash, k)
+            })
+            .collect();
+        // ties keep their given order so a repeated key is only removed by its first occurrence
+        removals.sort_unstable_by_key(|(bucket_index, position, _, _)| (*bucket_index, *position));
+
+        let mut removed: Vec<Option<V>> = removals.iter().map(|_| None).collect();
+        for (_, position, hash, k) in removals {
+            removed[position] = self.find(hash, &k).map(|index| self.remove_at(index).1);
         }
-        None
+        removed
+    }
+
+    // takes entry `index` out of the table. the last entry moves into its place
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        self.index
+            .swap_remove(index, key_order(&self.entries, &self.hasher));
+        self.entries.swap_remove(index)
+    }
+
+    // whether inserting one more entry would push the table past its load factor
+    #[cfg(feature = "std")]
+    pub(crate) fn would_grow(&self) -> bool {
+        (self.entries.len() + 1) as f64 / self.index.number_of_buckets() as f64
+            > self.max_load_factor
+    }
+
+    // empties a single bucket, handing back its entries
+    #[cfg(feature = "std")]
+    pub(crate) fn take_bucket(&mut self, bucket_index: usize) -> Vec<(K, V)> {
+        let mut indices: Vec<usize> = self.index.chain(bucket_index).collect();
+        // removing from the back first means only entries outside this bucket move into the gaps
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices
+            .into_iter()
+            .map(|index| self.remove_at(index))
+            .collect()
+    }
+
+    // drops every entry `keep` returns false for, returning how many went
+    fn retain_entries<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.extract_entries(|k, v| !keep(k, v), |_, _| {})
+    }
+
+    /// Latency histograms for `get`, `insert` and `remove` on this table.
+    #[cfg(feature = "latency-histograms")]
+    pub fn latencies(&self) -> &OperationLatencies {
+        &self.latencies
+    }
+
+    /// Swaps the values stored under `a` and `b` in place, leaving the table untouched if either is missing.
+    pub fn swap_values(&mut self, a: &K, b: &K) -> Result<(), SwapValuesError> {
+        let index_a = self.locate(a).ok_or(SwapValuesError::FirstMissing)?;
+        let index_b = self.locate(b).ok_or(SwapValuesError::SecondMissing)?;
+
+        if index_a != index_b {
+            let (low, high) = (index_a.min(index_b), index_a.max(index_b));
+            let (front, back) = self.entries.split_at_mut(high);
+            core::mem::swap(&mut front[low].1, &mut back[0].1);
+        }
+        Ok(())
+    }
+
+    fn locate(&self, k: &K) -> Option<usize> {
+        let hash = self.hasher.hash(k);
+        self.find(hash, k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
+    /// The number of buckets. The table holds `capacity() * load_factor()` entries before it grows.
     pub fn capacity(&self) -> usize {
-        self.buckets.len()
+        self.index.number_of_buckets()
     }
 
     pub fn entry(&mut self, k: K) -> Entry<'_, K, V, H> {
-        if self.get(&k).is_some() {
-            Entry::Occupied { ht: self, k }
+        let hash = self.hasher.hash(&k);
+        match self.find(hash, &k) {
+            Some(index) => Entry::Occupied(OccupiedEntry { ht: self, index }),
+            None => Entry::Vacant(VacantEntry { ht: self, k, hash }),
+        }
+    }
+
+    /// Whether both tables hold exactly the same keys, regardless of their values.
+    pub fn keys_eq<V2, H2>(&self, other: &HashTable<K, V2, H2>) -> bool
+    where
+        H2: SimpleHasher<K>,
+    {
+        self.len() == other.len() && self.into_iter().all(|(k, _)| other.get(k).is_some())
+    }
+
+    /// Whether the tables have no keys in common. Iterates the smaller table and probes the larger one.
+    pub fn is_disjoint<V2, H2>(&self, other: &HashTable<K, V2, H2>) -> bool
+    where
+        H2: SimpleHasher<K>,
+    {
+        if self.len() <= other.len() {
+            self.into_iter().all(|(k, _)| other.get(k).is_none())
         } else {
-            Entry::Vacant { ht: self, k }
+            other.into_iter().all(|(k, _)| self.get(k).is_none())
         }
     }
 
-    pub fn into_keys(self) -> Keys<K> {
-        let mut keys = vec![];
-        for b in self.buckets {
-            for (k, _) in b {
-                keys.push(k);
+    /// Inner join on keys: yields every key present in both tables with both values. Iterates the
+    /// smaller table and probes the larger one.
+    pub fn join<'a, V2, H2>(
+        &'a self,
+        other: &'a HashTable<K, V2, H2>,
+    ) -> impl Iterator<Item = (&'a K, &'a V, &'a V2)> + 'a
+    where
+        H2: SimpleHasher<K>,
+    {
+        let joined: Box<dyn Iterator<Item = (&'a K, &'a V, &'a V2)> + 'a> =
+            if self.len() <= other.len() {
+                Box::new(
+                    self.into_iter()
+                        .filter_map(move |(k, v)| other.get(k).map(|v2| (k, v, v2))),
+                )
+            } else {
+                Box::new(
+                    other
+                        .into_iter()
+                        .filter_map(move |(k, v2)| self.get(k).map(|v| (k, v, v2))),
+                )
+            };
+        joined
+    }
+
+    /// Left join on keys: yields every entry of this table along with the other table's value, if any.
+    pub fn left_join<'a, V2, H2>(
+        &'a self,
+        other: &'a HashTable<K, V2, H2>,
+    ) -> impl Iterator<Item = (&'a K, &'a V, Option<&'a V2>)> + 'a
+    where
+        H2: SimpleHasher<K>,
+    {
+        self.into_iter().map(move |(k, v)| (k, v, other.get(k)))
+    }
+
+    /// Full outer join on keys: yields every key present in either table with whichever values exist.
+    pub fn outer_join<'a, V2, H2>(
+        &'a self,
+        other: &'a HashTable<K, V2, H2>,
+    ) -> impl Iterator<Item = (&'a K, Option<&'a V>, Option<&'a V2>)> + 'a
+    where
+        H2: SimpleHasher<K>,
+    {
+        let left = self
+            .into_iter()
+            .map(move |(k, v)| (k, Some(v), other.get(k)));
+        let right_only = other
+            .into_iter()
+            .filter(move |(k, _)| self.get(k).is_none())
+            .map(|(k, v2)| (k, None, Some(v2)));
+        left.chain(right_only)
+    }
+
+    /// Moves every entry `pred` returns true for into `dest`, overwriting values of keys `dest` already has.
+    /// Returns how many entries were moved.
+    pub fn drain_into<H2, F>(&mut self, dest: &mut HashTable<K, V, H2>, pred: F) -> usize
+    where
+        H2: SimpleHasher<K>,
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.extract_entries(pred, |k, v| {
+            dest.insert(k, v);
+        })
+    }
+
+    /// Removes every entry, keeping the table's buckets for reuse.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    /// Keeps only the entries `keep` returns true for.
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.retain_entries(keep);
+    }
+
+    /// Lazily removes and yields the entries `pred` returns true for. Entries the iterator hasn't
+    /// reached when it is dropped stay in the table.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, H, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        ExtractIf {
+            ht: self,
+            index: 0,
+            pred,
+        }
+    }
+
+    // takes every entry `pred` returns true for out of the table and hands it to `extracted`, returning
+    // how many were taken
+    fn extract_entries<P, F>(&mut self, mut pred: P, mut extracted: F) -> usize
+    where
+        P: FnMut(&K, &mut V) -> bool,
+        F: FnMut(K, V),
+    {
+        let mut removed = 0;
+        let mut index = 0;
+        while index < self.entries.len() {
+            let (k, v) = &mut self.entries[index];
+            if pred(k, v) {
+                // the last entry moves into this slot and hasn't been looked at yet, so the index stays put
+                let (k, v) = self.remove_at(index);
+                extracted(k, v);
+                removed += 1;
+            } else {
+                index += 1;
+            }
+        }
+        removed
+    }
+
+    /// Keeps only the entries `pred` returns true for, calling `pred` across rayon's thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn par_retain<F>(&mut self, pred: F)
+    where
+        K: Send,
+        V: Send,
+        F: Fn(&K, &mut V) -> bool + Sync,
+    {
+        let keep: Vec<bool> = self
+            .entries
+            .par_iter_mut()
+            .map(|(k, v)| pred(k, v))
+            .collect();
+        // removing from the back means only entries that are staying move into the gaps
+        for (index, keep) in keep.into_iter().enumerate().rev() {
+            if !keep {
+                self.remove_at(index);
             }
         }
-        Keys { inner: keys }
+    }
+
+    /// Splits the table into `n` tables which together hold every entry exactly once. An entry goes to
+    /// shard `(hash >> 32) % n`, where `hash` is this table's hash of its key, which is the same rule
+    /// `ShardedHashTable` uses. Panics if `n` is zero.
+    pub fn into_shards(self, n: usize) -> Vec<HashTable<K, V, H>>
+    where
+        H: Clone,
+    {
+        assert!(n > 0, "a table can't be split into zero shards");
+        let mut shards: Vec<HashTable<K, V, H>> = vec![];
+        for _ in 0..n {
+            let mut shard = HashTable::from_parts(10, self.hasher.clone());
+            shard.max_load_factor = self.max_load_factor;
+            shard.adaptive_load_factor = self.adaptive_load_factor.as_ref().map(|a| a.fresh());
+            shard.max_chain_length = self.max_chain_length;
+            shards.push(shard);
+        }
+
+        let hashes = self.index.hashes().iter().copied();
+        for ((k, v), hash) in self.entries.into_iter().zip(hashes) {
+            // keys are already unique so they can skip the duplicate check
+            shards[(hash >> 32) as usize % n]._insert(k, v, hash);
+        }
+        shards
+    }
+
+    /// Iterates the table one bucket at a time, yielding every bucket including empty ones.
+    ///
+    /// Which entries share a bucket, and their order within it, depends on the hasher and the table's
+    /// current capacity, so the grouping changes whenever the table resizes.
+    pub fn iter_buckets(&self) -> impl Iterator<Item = impl Iterator<Item = &(K, V)>> {
+        self.index
+            .chains()
+            .map(move |chain| chain.map(move |index| &self.entries[index]))
+    }
+
+    /// Like `iter_buckets`, but with mutable access to values. Keys stay shared because changing one
+    /// would leave it in the wrong bucket.
+    pub fn iter_buckets_mut(&mut self) -> impl Iterator<Item = impl Iterator<Item = (&K, &mut V)>> {
+        // every entry is chained into exactly one bucket, so each is handed out once
+        let mut entries: Vec<Option<&mut (K, V)>> = self.entries.iter_mut().map(Some).collect();
+        self.index.chains().map(move |chain| {
+            let bucket: Vec<(&K, &mut V)> = chain
+                .map(|index| {
+                    let (k, v) = entries[index]
+                        .take()
+                        .expect("every entry is chained into one bucket");
+                    (&*k, v)
+                })
+                .collect();
+            bucket.into_iter()
+        })
+    }
+
+    /// Takes the table apart into its buckets, its number of entries and its hasher. Entries are grouped
+    /// and ordered as the table chains them, so the layout is only meaningful alongside the same hasher.
+    pub fn into_raw_parts(self) -> (Vec<Vec<(K, V)>>, usize, H) {
+        let HashTable {
+            entries,
+            index,
+            hasher,
+            ..
+        } = self;
+        let total_entries = entries.len();
+        let mut entries: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+        let buckets = index
+            .chains()
+            .map(|chain| {
+                chain
+                    .map(|i| {
+                        entries[i]
+                            .take()
+                            .expect("every entry is chained into one bucket")
+                    })
+                    .collect()
+            })
+            .collect();
+        (buckets, total_entries, hasher)
+    }
+
+    /// Iterates entries with mutable access to values. Keys stay shared because changing one would
+    /// leave it in the wrong bucket.
+    pub fn iter_mut(&mut self) -> HashTableIterMut<'_, K, V> {
+        HashTableIterMut {
+            inner: self.entries.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.into_iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.into_iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Empties the table, handing back its entries. The table keeps its number of buckets, and the
+    /// entries are gone even if the iterator is dropped before reaching the end.
+    pub fn drain(&mut self) -> HashTableIntoIter<K, V> {
+        self.index.clear();
+        HashTableIntoIter {
+            inner: core::mem::take(&mut self.entries).into_iter(),
+        }
+    }
+
+    pub fn into_keys(self) -> Keys<K> {
+        Keys {
+            inner: self.entries.into_iter().map(|(k, _)| k).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapValuesError {
+    FirstMissing,
+    SecondMissing,
+}
+
+impl core::fmt::Display for SwapValuesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SwapValuesError::FirstMissing => write!(f, "the first key is not in the table"),
+            SwapValuesError::SecondMissing => write!(f, "the second key is not in the table"),
+        }
     }
 }
 
+impl core::error::Error for SwapValuesError {}
+
+/// A key that appeared more than once where keys have to be unique.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError<K> {
+    key: K,
+}
+
+impl<K> DuplicateKeyError<K> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+}
+
+impl<K: core::fmt::Debug> core::fmt::Display for DuplicateKeyError<K> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "duplicate key {:?}", self.key)
+    }
+}
+
+impl<K: core::fmt::Debug> core::error::Error for DuplicateKeyError<K> {}
+
 pub struct Keys<K> {
     inner: Vec<K>,
 }
@@ -209,7 +1193,7 @@ impl<K> IntoIterator for Keys<K> {
 impl<'a, K> IntoIterator for &'a Keys<K> {
     type Item = &'a K;
 
-    type IntoIter = std::slice::Iter<'a, K>;
+    type IntoIter = core::slice::Iter<'a, K>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.inner.iter()
@@ -221,14 +1205,8 @@ where
     K: Hash,
     H: SimpleHasher<K>,
 {
-    Occupied {
-        ht: &'a mut HashTable<K, V, H>,
-        k: K,
-    },
-    Vacant {
-        ht: &'a mut HashTable<K, V, H>,
-        k: K,
-    },
+    Occupied(OccupiedEntry<'a, K, V, H>),
+    Vacant(VacantEntry<'a, K, V, H>),
 }
 
 impl<'a, K, V, H> Entry<'a, K, V, H>
@@ -236,23 +1214,273 @@ where
     K: PartialEq + Hash,
     H: SimpleHasher<K>,
 {
-    pub fn or_insert(self, v: V) -> &'a mut V {
+    pub fn key(&self) -> &K {
         match self {
-            Entry::Occupied { k, ht } => {
-                let e = ht.get_mut(&k);
-                e.unwrap()
-            }
-            Entry::Vacant { k, ht } => {
-                let hash = ht.hasher.hash(&k);
-                ht._insert(k, v, hash)
-            }
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key(),
         }
     }
-}
+
+    pub fn or_insert(self, v: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(v),
+        }
+    }
+
+    /// Like `or_insert`, but only builds the value when the key is missing.
+    pub fn or_insert_with<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(f()),
+        }
+    }
+
+    /// Like `or_insert_with`, but builds the value from the key.
+    pub fn or_insert_with_key<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce(&K) -> V,
+    {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let v = f(e.key());
+                e.insert(v)
+            }
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Runs `f` on the value if the key is present, handing the entry back either way so it can be
+    /// chained with `or_insert` and friends.
+    pub fn and_modify<F>(self, f: F) -> Entry<'a, K, V, H>
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+
+    /// Writes `v` whether or not the key was present, handing back the now occupied entry.
+    /// An occupied entry keeps its original key; use `OccupiedEntry::insert` directly when the old value is needed.
+    pub fn insert(self, v: V) -> OccupiedEntry<'a, K, V, H> {
+        match self {
+            Entry::Occupied(mut e) => {
+                e.insert(v);
+                e
+            }
+            Entry::Vacant(e) => e.insert_entry(v),
+        }
+    }
+}
+
+pub struct OccupiedEntry<'a, K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    ht: &'a mut HashTable<K, V, H>,
+    index: usize,
+}
+
+impl<'a, K, V, H> OccupiedEntry<'a, K, V, H>
+where
+    K: PartialEq + Hash,
+    H: SimpleHasher<K>,
+{
+    pub fn key(&self) -> &K {
+        let (k, _) = &self.ht.entries[self.index];
+        k
+    }
+
+    pub fn get(&self) -> &V {
+        let (_, v) = &self.ht.entries[self.index];
+        v
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        let (_, v) = &mut self.ht.entries[self.index];
+        v
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        let (_, v) = &mut self.ht.entries[self.index];
+        v
+    }
+
+    /// Replaces the entry's value, returning the old one.
+    pub fn insert(&mut self, v: V) -> V {
+        core::mem::replace(self.get_mut(), v)
+    }
+
+    /// Takes the entry out of the table, returning its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Takes the entry out of the table, returning its key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        self.ht.remove_at(self.index)
+    }
+}
+
+pub struct VacantEntry<'a, K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    ht: &'a mut HashTable<K, V, H>,
+    k: K,
+    hash: u64,
+}
+
+impl<'a, K, V, H> VacantEntry<'a, K, V, H>
+where
+    K: PartialEq + Hash,
+    H: SimpleHasher<K>,
+{
+    pub fn key(&self) -> &K {
+        &self.k
+    }
+
+    /// Gives back the key moved into the entry without inserting anything.
+    pub fn into_key(self) -> K {
+        self.k
+    }
+
+    /// Inserts `v` under the entry's key without searching for it again.
+    pub fn insert(self, v: V) -> &'a mut V {
+        self.insert_entry(v).into_mut()
+    }
+
+    fn insert_entry(self, v: V) -> OccupiedEntry<'a, K, V, H> {
+        let index = self.ht._insert(self.k, v, self.hash);
+        OccupiedEntry { ht: self.ht, index }
+    }
+}
+
+/// The clone starts with nothing recorded in its latency histograms or adaptive load factor.
+impl<K, V, H> Clone for HashTable<K, V, H>
+where
+    K: Hash + Clone,
+    V: Clone,
+    H: SimpleHasher<K> + Clone,
+{
+    fn clone(&self) -> Self {
+        let mut hash_table = HashTable::from_parts(0, self.hasher.clone());
+        hash_table.entries = self.entries.clone();
+        hash_table.index = self.index.clone();
+        hash_table.max_load_factor = self.max_load_factor;
+        hash_table.adaptive_load_factor = self.adaptive_load_factor.as_ref().map(|a| a.fresh());
+        hash_table.max_chain_length = self.max_chain_length;
+        hash_table.chain_length_resizes = self.chain_length_resizes;
+        #[cfg(feature = "probe-length-assertions")]
+        {
+            hash_table.max_probe_length = self.max_probe_length;
+        }
+        hash_table
+    }
+}
+
+impl<K, V, H> core::fmt::Debug for HashTable<K, V, H>
+where
+    K: Hash + core::fmt::Debug,
+    V: core::fmt::Debug,
+    H: SimpleHasher<K>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map()
+            .entries(self.into_iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+/// Tables are equal when they hold the same keys mapped to equal values, whatever their capacity or
+/// hasher.
+impl<K, V, H, H2> PartialEq<HashTable<K, V, H2>> for HashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    V: PartialEq,
+    H: SimpleHasher<K>,
+    H2: SimpleHasher<K>,
+{
+    fn eq(&self, other: &HashTable<K, V, H2>) -> bool {
+        self.len() == other.len() && self.into_iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K, V, H> Eq for HashTable<K, V, H>
+where
+    K: Hash + Eq,
+    V: Eq,
+    H: SimpleHasher<K>,
+{
+}
+
+/// A repeated key keeps its last value.
+impl<K, V> core::iter::FromIterator<(K, V)> for HashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    fn from_iter<I>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut hash_table = HashTable::new();
+        hash_table.extend(pairs);
+        hash_table
+    }
+}
+
+/// Grows once up front for the lower bound of the iterator's size hint. A repeated key keeps its last
+/// value.
+impl<K, V, H> Extend<(K, V)> for HashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    fn extend<I>(&mut self, pairs: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let pairs = pairs.into_iter();
+        self.reserve_exact(pairs.size_hint().0);
+        for (k, v) in pairs {
+            self.insert(k, v);
+        }
+    }
+}
+
+/// Panics if the key is missing.
+impl<K, Q, V, H> core::ops::Index<&Q> for HashTable<K, V, H>
+where
+    K: Hash + PartialEq + Borrow<Q>,
+    Q: Hash + PartialEq + ?Sized,
+    H: SimpleHasher<K> + SimpleHasher<Q>,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
 
 pub struct HashTableIterator<'a, K, V> {
-    elements_iterator: Box<dyn Iterator<Item = &'a (K, V)> + 'a>,
-    buckets_iterator: Box<dyn Iterator<Item = &'a Vec<(K, V)>> + 'a>,
+    inner: core::slice::Iter<'a, (K, V)>,
 }
 
 impl<'a, K: Hash, V, H: SimpleHasher<K>> IntoIterator for &'a HashTable<K, V, H> {
@@ -261,15 +1489,8 @@ impl<'a, K: Hash, V, H: SimpleHasher<K>> IntoIterator for &'a HashTable<K, V, H>
     type IntoIter = HashTableIterator<'a, K, V>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let mut buckets_iterator = self.buckets.iter();
-        // first elements iterator needs to be initialized
-        let elements_iterator = buckets_iterator
-            .next()
-            .map(|bi| bi.iter())
-            .unwrap_or_else(|| [].iter());
         HashTableIterator {
-            elements_iterator: Box::new(elements_iterator),
-            buckets_iterator: Box::new(buckets_iterator),
+            inner: self.entries.iter(),
         }
     }
 }
@@ -278,74 +1499,536 @@ impl<'a, K, V> Iterator for HashTableIterator<'a, K, V> {
     type Item = &'a (K, V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.elements_iterator.next().or_else(|| {
-            // no element available in this bucket
-            // iterating to next bucket and either
-            // ending iteration or recursing
-            self.buckets_iterator.next().and_then(|b| {
-                // bucket is available so we are recursing
-                let elements_iterator = b.iter();
-                self.elements_iterator = Box::new(elements_iterator);
-                self.next()
-            })
-        })
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct HashTableIterMut<'a, K, V> {
+    inner: core::slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for HashTableIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.inner.next()?;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct HashTableIntoIter<K, V> {
+    inner: IntoIter<(K, V)>,
+}
+
+impl<K: Hash, V, H: SimpleHasher<K>> IntoIterator for HashTable<K, V, H> {
+    type Item = (K, V);
+
+    type IntoIter = HashTableIntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        HashTableIntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+impl<K, V> Iterator for HashTableIntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct ExtractIf<'a, K, V, H, F>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    ht: &'a mut HashTable<K, V, H>,
+    index: usize,
+    pred: F,
+}
+
+impl<'a, K, V, H, F> Iterator for ExtractIf<'a, K, V, H, F>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.ht.entries.len() {
+            let (k, v) = &mut self.ht.entries[self.index];
+            if (self.pred)(k, v) {
+                // the last entry moves into the gap and hasn't been looked at yet, so the index stays put
+                return Some(self.ht.remove_at(self.index));
+            }
+            self.index += 1;
+        }
+        None
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::hash::Hash;
+    use std::hash::{Hash, Hasher};
+
+    use crate::{Entry, HashTable, SimpleHasher, SwapValuesError};
+
+    #[derive(PartialEq, PartialOrd, Debug, Eq, Clone, Ord)]
+    struct User {
+        name: String,
+        age: i32,
+    }
+
+    #[test]
+    fn insert_and_get_key_value_pair() {
+        let mut hash_table = HashTable::new();
+        hash_table.insert(
+            "gedalia",
+            User {
+                name: "gedalia".to_string(),
+                age: 27,
+            },
+        );
+
+        let result = hash_table.get(&"gedalia");
+        let gedalia = User {
+            name: "gedalia".to_string(),
+            age: 27,
+        };
+        let expected_result = Some(&gedalia);
+
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_get_key_parameter_is_not_moved() {
+        let mut hash_table = HashTable::new();
+
+        let gedalia_string = String::from("gedalia");
+
+        let gedalia = User {
+            name: gedalia_string.clone(),
+            age: 27,
+        };
+
+        hash_table.insert(gedalia_string.clone(), gedalia.clone());
+
+        let result = hash_table.get(&gedalia_string);
+        let expected_result = Some(&gedalia);
+
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_borrowed_key_lookups() {
+        let mut hash_table = HashTable::new();
+        hash_table.insert(String::from("gedalia"), 27);
+        hash_table.insert(String::from("theo"), 28);
+
+        assert_eq!(hash_table.get("gedalia"), Some(&27));
+        assert_eq!(hash_table.get_cloned("theo"), Some(28));
+        *hash_table.get_mut("theo").unwrap() += 1;
+        assert_eq!(hash_table.remove("theo"), Some(29));
+        assert_eq!(hash_table.get("theo"), None);
+
+        let mut hash_table = HashTable::new();
+        hash_table.insert(vec![1, 2, 3], "abc");
+        assert_eq!(hash_table.get(&[1, 2, 3][..]), Some(&"abc"));
+        assert_eq!(hash_table.get(&[1, 2][..]), None);
+    }
+
+    #[test]
+    fn test_collisions() {
+        struct SillyHasher;
+        impl<K> SimpleHasher<K> for SillyHasher
+        where
+            K: Hash,
+        {
+            fn hash(&self, _: &K) -> u64 {
+                0
+            }
+        }
+
+        // The SillyHasher hashes keys to a constant value of 0. We store all entries in the zeroth bucket.
+        // This test uses the SillyHasher to force collisions to occur so we can assert that all key-value pairs
+        // are addressable individually even when all key hashes collide.
+        let mut hash_table = HashTable::with_hasher(SillyHasher {});
+        hash_table.insert(
+            "gedalia",
+            User {
+                name: "gedalia".to_string(),
+                age: 27,
+            },
+        );
+
+        hash_table.insert(
+            "theo",
+            User {
+                name: "theo".to_string(),
+                age: 0,
+            },
+        );
+
+        let gedalia_result = hash_table.get(&"gedalia");
+        let theo_result = hash_table.get(&"theo");
+
+        let gedalia = User {
+            name: "gedalia".to_string(),
+            age: 27,
+        };
+        let expected_gedalia_result = Some(&gedalia);
+        let theo = User {
+            name: "theo".to_string(),
+            age: 0,
+        };
+        let expected_theo_result = Some(&theo);
+
+        assert_eq!(gedalia_result, expected_gedalia_result);
+        assert_eq!(theo_result, expected_theo_result);
+    }
+
+    #[test]
+    fn test_keys_are_hashed_once() {
+        struct CountingHasher {
+            calls: core::cell::Cell<usize>,
+        }
+        impl SimpleHasher<u64> for CountingHasher {
+            fn hash(&self, k: &u64) -> u64 {
+                self.calls.set(self.calls.get() + 1);
+                // a handful of distinct hashes so some chains get long enough to be sorted
+                k % 64
+            }
+        }
+
+        let mut hash_table = HashTable::with_hasher(CountingHasher {
+            calls: core::cell::Cell::new(0),
+        });
+        // resizing along the way reuses the hashes the table already has
+        for k in 0..1000 {
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.hasher.calls.get(), 1000);
+
+        for k in 0..1000 {
+            assert_eq!(hash_table.get(&k), Some(&k));
+        }
+        assert_eq!(hash_table.hasher.calls.get(), 2000);
+
+        for k in 0..1000 {
+            assert_eq!(hash_table.remove(&k), Some(k));
+        }
+        assert_eq!(hash_table.hasher.calls.get(), 3000);
+    }
+
+    #[test]
+    fn test_long_chains_are_sorted_and_searchable() {
+        // spreads hashes apart while still sending every key to the zeroth bucket at any
+        // capacity this test reaches, so one chain grows far beyond SORTED_CHAIN_THRESHOLD
+        struct ChainingHasher;
+        impl SimpleHasher<u64> for ChainingHasher {
+            fn hash(&self, k: &u64) -> u64 {
+                k.wrapping_mul(10 << 30)
+            }
+        }
+
+        let mut hash_table = HashTable::with_hasher(ChainingHasher {});
+        for k in (0..200).rev() {
+            hash_table.insert(k, k * 2);
+        }
+
+        let chain: Vec<u64> = hash_table
+            .iter_buckets()
+            .next()
+            .unwrap()
+            .map(|(k, _)| *k)
+            .collect();
+        assert_eq!(chain.len(), 200);
+        assert!(chain.windows(2).all(|w| w[0] < w[1]));
+
+        for k in 0..200 {
+            assert_eq!(hash_table.get(&k), Some(&(k * 2)));
+        }
+        assert_eq!(hash_table.get(&200), None);
+
+        assert_eq!(hash_table.insert(42, 0), Some(84));
+        *hash_table.get_mut(&43).unwrap() = 1;
+        assert_eq!(hash_table.get(&42), Some(&0));
+        assert_eq!(hash_table.get(&43), Some(&1));
+
+        // shrink the chain back below the threshold and grow it past it again
+        for k in 5..200 {
+            assert!(hash_table.remove(&k).is_some());
+        }
+        assert_eq!(hash_table.remove(&5), None);
+        assert_eq!(hash_table.iter_buckets().next().unwrap().count(), 5);
+        for k in (5..20).rev() {
+            hash_table.insert(k, k);
+        }
+        let chain: Vec<u64> = hash_table
+            .iter_buckets()
+            .next()
+            .unwrap()
+            .map(|(k, _)| *k)
+            .collect();
+        assert!(chain.windows(2).all(|w| w[0] < w[1]));
+        for k in 0..20 {
+            assert!(hash_table.get(&k).is_some());
+        }
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut hash_table = HashTable::new();
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("theo", 0);
+
+        assert_eq!(hash_table.remove(&"gedalia"), Some(27));
+        assert_eq!(hash_table.remove(&"gedalia"), None);
+        assert_eq!(hash_table.get(&"gedalia"), None);
+        assert_eq!(hash_table.get(&"theo"), Some(&0));
+    }
+
+    #[test]
+    fn test_remove_batch() {
+        let mut hash_table = HashTable::new();
+        for k in 0..100 {
+            hash_table.insert(k, k * 2);
+        }
+
+        let removed = hash_table.remove_batch(vec![99, 3, 1000, 3, 42]);
+        assert_eq!(removed, vec![Some(198), Some(6), None, None, Some(84)]);
+        assert_eq!(hash_table.len(), 97);
+        for k in [3, 42, 99].iter() {
+            assert_eq!(hash_table.get(k), None);
+        }
+        assert_eq!(hash_table.get(&4), Some(&8));
+    }
+
+    #[test]
+    fn test_chain_length_triggered_resize() {
+        // every key lands in the zeroth of 10 buckets, but doubling to 20 splits them by parity
+        struct TensHasher;
+        impl SimpleHasher<u64> for TensHasher {
+            fn hash(&self, k: &u64) -> u64 {
+                k * 10
+            }
+        }
+
+        let mut hash_table = HashTable::with_hasher(TensHasher {}).with_max_chain_length(4);
+        for k in 0..4 {
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.capacity(), 10);
+        assert_eq!(hash_table.chain_length_resizes(), 0);
+
+        hash_table.insert(4, 4);
+        assert_eq!(hash_table.capacity(), 20);
+        assert_eq!(hash_table.chain_length_resizes(), 1);
+        for k in 0..5 {
+            assert_eq!(hash_table.get(&k), Some(&k));
+        }
+
+        // a hasher that cannot be helped by growing never triggers a resize
+        struct SillyHasher;
+        impl SimpleHasher<u64> for SillyHasher {
+            fn hash(&self, _: &u64) -> u64 {
+                0
+            }
+        }
+
+        let mut hash_table = HashTable::with_hasher(SillyHasher {}).with_max_chain_length(4);
+        for k in 0..7 {
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.capacity(), 10);
+        assert_eq!(hash_table.chain_length_resizes(), 0);
+    }
+
+    #[cfg(feature = "probe-length-assertions")]
+    #[test]
+    #[should_panic(expected = "compared 4 keys, over the limit of 3")]
+    fn test_max_probe_length() {
+        struct SillyHasher;
+        impl SimpleHasher<u64> for SillyHasher {
+            fn hash(&self, _: &u64) -> u64 {
+                0
+            }
+        }
+
+        let mut hash_table = HashTable::with_hasher(SillyHasher {}).with_max_probe_length(3);
+        for k in 0..3 {
+            hash_table.insert(k, k);
+        }
+        // looking for a fourth key has to compare against all three, so inserting it is fine
+        hash_table.insert(3, 3);
+        // but missing now compares against all four
+        hash_table.get(&4);
+    }
 
-    use crate::{HashTable, SimpleHasher};
+    #[test]
+    fn test_reserve_exact() {
+        let mut hash_table = HashTable::with_capacity(7);
+        hash_table.insert(0, 0);
+        hash_table.insert(1, 1);
+
+        // already big enough
+        hash_table.reserve_exact(5);
+        assert_eq!(hash_table.capacity(), 10);
+
+        // 12 entries at a 0.75 load factor need exactly 16 buckets
+        hash_table.reserve_exact(10);
+        assert_eq!(hash_table.capacity(), 16);
+        for k in 2..12 {
+            hash_table.insert(k, k);
+            assert_eq!(hash_table.capacity(), 16);
+        }
+        for k in 0..12 {
+            assert_eq!(hash_table.get(&k), Some(&k));
+        }
 
-    #[derive(PartialEq, PartialOrd, Debug, Eq, Clone, Ord)]
-    struct User {
-        name: String,
-        age: i32,
+        hash_table.insert(12, 12);
+        assert_eq!(hash_table.capacity(), 32);
     }
 
     #[test]
-    fn insert_and_get_key_value_pair() {
-        let mut hash_table = HashTable::new();
-        hash_table.insert(
-            "gedalia",
-            User {
-                name: "gedalia".to_string(),
-                age: 27,
-            },
-        );
+    fn test_reserve_and_shrink_to_fit() {
+        let mut hash_table = HashTable::new().with_load_factor(2.0);
+        hash_table.reserve(100);
+        assert_eq!(hash_table.capacity(), 50);
+        for k in 0..100 {
+            hash_table.insert(k, k);
+            assert_eq!(hash_table.capacity(), 50);
+        }
 
-        let result = hash_table.get(&"gedalia");
-        let gedalia = User {
-            name: "gedalia".to_string(),
-            age: 27,
-        };
-        let expected_result = Some(&gedalia);
+        // a small reserve still doubles
+        hash_table.reserve(1);
+        assert_eq!(hash_table.capacity(), 100);
+        assert!(hash_table.try_reserve(10).is_ok());
+        assert_eq!(hash_table.capacity(), 100);
+        assert!(hash_table.try_reserve(usize::MAX).is_err());
+        assert_eq!(hash_table.capacity(), 100);
 
-        assert_eq!(result, expected_result);
+        for k in 10..100 {
+            hash_table.remove(&k);
+        }
+        hash_table.shrink_to_fit();
+        assert_eq!(hash_table.capacity(), 5);
+        for k in 0..10 {
+            assert_eq!(hash_table.get(&k), Some(&k));
+        }
+
+        let mut hash_table = HashTable::with_capacity(1000);
+        let buckets = hash_table.capacity();
+        for k in 0..1000 {
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.capacity(), buckets);
     }
 
+    #[cfg(feature = "rayon")]
     #[test]
-    fn test_get_key_parameter_is_not_moved() {
+    fn test_par_reserve_exact() {
         let mut hash_table = HashTable::new();
+        for k in 0..1000 {
+            hash_table.insert(k, k * 2);
+        }
 
-        let gedalia_string = String::from("gedalia");
+        hash_table.par_reserve_exact(99_000);
+        // 100k entries at a 0.75 load factor
+        assert_eq!(hash_table.capacity(), 133_334);
+        for k in 0..1000 {
+            assert_eq!(hash_table.get(&k), Some(&(k * 2)));
+        }
+        for k in 1000..100_000 {
+            hash_table.insert(k, k * 2);
+        }
+        assert_eq!(hash_table.capacity(), 133_334);
+        assert_eq!(hash_table.get(&99_999), Some(&199_998));
+    }
 
-        let gedalia = User {
-            name: gedalia_string.clone(),
-            age: 27,
-        };
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_retain() {
+        let mut hash_table = HashTable::new();
+        for k in 0..10_000 {
+            hash_table.insert(k, k);
+        }
 
-        hash_table.insert(gedalia_string.clone(), gedalia.clone());
+        hash_table.par_retain(|k, v| {
+            *v += 1;
+            k % 3 == 0
+        });
+
+        assert_eq!(hash_table.len(), 3334);
+        for k in 0..10_000 {
+            if k % 3 == 0 {
+                assert_eq!(hash_table.get(&k), Some(&(k + 1)));
+            } else {
+                assert_eq!(hash_table.get(&k), None);
+            }
+        }
+    }
 
-        let result = hash_table.get(&gedalia_string);
-        let expected_result = Some(&gedalia);
+    #[test]
+    fn test_joins() {
+        let mut ages = HashTable::new();
+        ages.insert("gedalia", 27);
+        ages.insert("theo", 0);
+        ages.insert("aviva", 26);
+
+        let mut cities = HashTable::new();
+        cities.insert("gedalia", "new york");
+        cities.insert("chani", "boston");
+
+        let mut inner: Vec<_> = ages.join(&cities).collect();
+        assert_eq!(inner, vec![(&"gedalia", &27, &"new york")]);
+        // probing from the other side gives the same rows
+        inner = cities.join(&ages).map(|(k, c, a)| (k, a, c)).collect();
+        assert_eq!(inner, vec![(&"gedalia", &27, &"new york")]);
+
+        let mut left: Vec<_> = ages.left_join(&cities).collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                (&"aviva", &26, None),
+                (&"gedalia", &27, Some(&"new york")),
+                (&"theo", &0, None),
+            ]
+        );
 
-        assert_eq!(result, expected_result);
+        let mut outer: Vec<_> = ages.outer_join(&cities).collect();
+        outer.sort();
+        assert_eq!(
+            outer,
+            vec![
+                (&"aviva", Some(&26), None),
+                (&"chani", None, Some(&"boston")),
+                (&"gedalia", Some(&27), Some(&"new york")),
+                (&"theo", Some(&0), None),
+            ]
+        );
     }
 
     #[test]
-    fn test_collisions() {
+    fn test_drain_into() {
+        // the destination may use a different hasher
         struct SillyHasher;
         impl<K> SimpleHasher<K> for SillyHasher
         where
@@ -356,49 +2039,85 @@ mod tests {
             }
         }
 
-        // The SillyHasher hashes keys to a constant value of 0. We store all entries in the zeroth bucket.
-        // This test uses the SillyHasher to force collisions to occur so we can assert that all key-value pairs
-        // are addressable individually even when all key hashes collide.
-        let mut hash_table = HashTable::with_hasher(SillyHasher {});
-        hash_table.insert(
-            "gedalia",
-            User {
-                name: "gedalia".to_string(),
-                age: 27,
-            },
-        );
+        let mut active = HashTable::new();
+        for (name, age) in [("gedalia", 27), ("theo", 0), ("aviva", 26), ("chani", 25)].iter() {
+            active.insert(*name, *age);
+        }
+        let mut expired = HashTable::with_hasher(SillyHasher {});
+        expired.insert("aviva", -1);
+
+        let moved = active.drain_into(&mut expired, |_, age| *age > 20);
+        assert_eq!(moved, 3);
+        assert_eq!(active.len(), 1);
+        assert_eq!(expired.len(), 3);
+        assert_eq!(active.get(&"theo"), Some(&0));
+        assert_eq!(active.get(&"gedalia"), None);
+        assert_eq!(expired.get(&"gedalia"), Some(&27));
+        assert_eq!(expired.get(&"aviva"), Some(&26));
+        assert_eq!(expired.get(&"chani"), Some(&25));
+    }
 
-        hash_table.insert(
-            "theo",
-            User {
-                name: "theo".to_string(),
-                age: 0,
-            },
-        );
+    #[test]
+    fn test_clear_and_retain() {
+        let mut hash_table = HashTable::new();
+        for k in 0..100 {
+            hash_table.insert(k, k * 10);
+        }
+        assert_eq!(hash_table.len(), 100);
+
+        hash_table.retain(|k, v| {
+            *v += 1;
+            k % 3 == 0
+        });
+        assert_eq!(hash_table.len(), 34);
+        assert_eq!(hash_table.get(&3), Some(&31));
+        assert_eq!(hash_table.get(&4), None);
+
+        let capacity = hash_table.capacity();
+        hash_table.clear();
+        assert!(hash_table.is_empty());
+        assert_eq!(hash_table.capacity(), capacity);
+        assert_eq!(hash_table.get(&3), None);
+    }
 
-        let gedalia_result = hash_table.get(&"gedalia");
-        let theo_result = hash_table.get(&"theo");
+    #[test]
+    fn test_extract_if() {
+        // one long chain that has to stay sorted while entries are taken out of it
+        struct SillyHasher;
+        impl SimpleHasher<i32> for SillyHasher {
+            fn hash(&self, k: &i32) -> u64 {
+                (k % 2) as u64
+            }
+        }
 
-        let gedalia = User {
-            name: "gedalia".to_string(),
-            age: 27,
-        };
-        let expected_gedalia_result = Some(&gedalia);
-        let theo = User {
-            name: "theo".to_string(),
-            age: 0,
-        };
-        let expected_theo_result = Some(&theo);
+        let mut hash_table = HashTable::with_hasher(SillyHasher {});
+        for k in 0..40 {
+            hash_table.insert(k, k);
+        }
 
-        assert_eq!(gedalia_result, expected_gedalia_result);
-        assert_eq!(theo_result, expected_theo_result);
+        let mut evens: Vec<(i32, i32)> = hash_table.extract_if(|k, _| k % 4 == 0).collect();
+        evens.sort_unstable();
+        assert_eq!(
+            evens,
+            (0..40).step_by(4).map(|k| (k, k)).collect::<Vec<_>>()
+        );
+        assert_eq!(hash_table.len(), 30);
+        for k in 0..40 {
+            assert_eq!(hash_table.get(&k).is_some(), k % 4 != 0);
+        }
+
+        // dropping the iterator early leaves the entries it never reached
+        assert!(hash_table.extract_if(|_, _| true).next().is_some());
+        assert_eq!(hash_table.len(), 29);
+        assert_eq!(hash_table.into_iter().count(), 29);
     }
 
     #[test]
     fn test_dynamic_resizing() {
-        let mut hash_table = HashTable::with_capacity(9);
+        // six entries at a 0.75 load factor need 8 buckets
+        let mut hash_table = HashTable::with_capacity(6);
 
-        assert_eq!(hash_table.capacity(), 9);
+        assert_eq!(hash_table.capacity(), 8);
 
         let users = vec![
             User {
@@ -429,7 +2148,7 @@ mod tests {
 
         for user in users {
             hash_table.insert(user.name.to_string(), user);
-            assert_eq!(hash_table.capacity(), 9);
+            assert_eq!(hash_table.capacity(), 8);
         }
 
         hash_table.insert(
@@ -440,9 +2159,9 @@ mod tests {
             },
         );
 
-        assert_ne!(hash_table.capacity(), 9);
-        assert!(hash_table.capacity() > 9);
-        assert_eq!(hash_table.capacity(), 18);
+        assert_ne!(hash_table.capacity(), 8);
+        assert!(hash_table.capacity() > 8);
+        assert_eq!(hash_table.capacity(), 16);
 
         let gedalia_result = hash_table.get(&String::from("gedalia"));
         let caine_result = hash_table.get(&String::from("caine"));
@@ -506,7 +2225,7 @@ mod tests {
         for (k, v) in &hash_table {
             let found = &users.binary_search(v);
             assert!(found.is_ok());
-            assert!(found.map(|i| &users[i].name == k).unwrap() == true);
+            assert!(found.map(|i| &users[i].name == k).unwrap());
         }
 
         let nowhere_man = User {
@@ -526,6 +2245,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_iteration_over_sparse_hash_table() {
+        // long runs of empty buckets used to cost a stack frame each
+        let mut hash_table = HashTable::with_capacity(1_000_000);
+        hash_table.insert(1, 1);
+        hash_table.insert(999_999, 2);
+
+        let mut values: Vec<i32> = (&hash_table).into_iter().map(|(_, v)| *v).collect();
+        values.sort();
+        assert_eq!(values, vec![1, 2]);
+        assert_eq!((&HashTable::<i32, i32>::new()).into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_standard_traits() {
+        let mut hash_table: HashTable<&str, i32> =
+            vec![("gedalia", 27), ("theo", 28)].into_iter().collect();
+        assert_eq!(hash_table["gedalia"], 27);
+        assert_eq!(hash_table[&"theo"], 28);
+
+        let copy = hash_table.clone();
+        hash_table.extend(vec![("aviva", 29), ("theo", 30)]);
+        assert_eq!(hash_table["theo"], 30);
+        assert_eq!(copy["theo"], 28);
+        assert_ne!(hash_table, copy);
+
+        let mut rebuilt = HashTable::with_capacity(100);
+        rebuilt.extend(vec![("theo", 30), ("gedalia", 27), ("aviva", 29)]);
+        assert_eq!(hash_table, rebuilt);
+        rebuilt.remove("aviva");
+        assert_ne!(hash_table, rebuilt);
+
+        let single: HashTable<&str, i32> = vec![("gedalia", 27)].into_iter().collect();
+        assert_eq!(format!("{:?}", single), r#"{"gedalia": 27}"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "no entry found for key")]
+    fn test_index_panics_on_missing_key() {
+        let hash_table: HashTable<&str, i32> = HashTable::new();
+        let _ = hash_table["gedalia"];
+    }
+
+    #[test]
+    fn test_owned_and_mutable_iteration() {
+        let mut hash_table = HashTable::new();
+        for k in 0..20 {
+            hash_table.insert(k, k * 10);
+        }
+
+        for (k, v) in hash_table.iter_mut() {
+            *v += k;
+        }
+        for v in hash_table.values_mut() {
+            *v += 1;
+        }
+        let mut keys: Vec<i32> = hash_table.keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys, (0..20).collect::<Vec<_>>());
+        assert_eq!(
+            hash_table.values().sum::<i32>(),
+            (0..20).map(|k| k * 11 + 1).sum::<i32>()
+        );
+
+        let mut drained: Vec<(i32, i32)> = hash_table.drain().take(5).collect();
+        assert_eq!(drained.len(), 5);
+        assert_eq!(hash_table.keys().count(), 0);
+        assert_eq!(hash_table.get(&0), None);
+
+        hash_table.insert(1, 1);
+        hash_table.insert(2, 2);
+        drained = hash_table.into_iter().collect();
+        drained.sort();
+        assert_eq!(drained, vec![(1, 1), (2, 2)]);
+    }
+
     #[test]
     fn test_entry_interface() {
         let mut hash_table = HashTable::new();
@@ -538,12 +2333,96 @@ mod tests {
         let user_entry = hash_table.entry("gedalia");
         let user = user_entry.or_insert(g_backup);
         // user_entry; // should not compile if uncommented since or_insert moves the entry (consumed)
-        (*user).age += 100;
+        user.age += 100;
 
         let user = hash_table.get(&"gedalia");
         assert_eq!(user.unwrap().age, 127);
     }
 
+    #[test]
+    fn test_entry_insert() {
+        let mut hash_table = HashTable::new();
+
+        let g = User {
+            name: "gedalia".to_string(),
+            age: 27,
+        };
+
+        // vacant entries are filled and come back occupied
+        let mut entry = hash_table.entry("gedalia").insert(g);
+        assert_eq!(entry.get().age, 27);
+        entry.get_mut().age += 1;
+
+        let older_g = User {
+            name: "gedalia".to_string(),
+            age: 100,
+        };
+
+        // occupied entries are overwritten and the old value is reachable through the entry
+        let mut entry = hash_table.entry("gedalia").insert(older_g);
+        let old_user = entry.insert(User {
+            name: "gedalia".to_string(),
+            age: 127,
+        });
+        assert_eq!(old_user.age, 100);
+
+        assert_eq!(hash_table.get(&"gedalia").unwrap().age, 127);
+    }
+
+    #[test]
+    fn test_vacant_entry_into_key() {
+        let mut hash_table: HashTable<String, User> = HashTable::new();
+
+        let key = match hash_table.entry("gedalia".to_string()) {
+            Entry::Vacant(e) => e.into_key(),
+            Entry::Occupied(_) => panic!("nothing has been inserted yet"),
+        };
+
+        assert_eq!(key, "gedalia");
+        assert_eq!(hash_table.get(&key), None);
+    }
+
+    #[test]
+    fn test_entry_combinators() {
+        let mut counts: HashTable<&str, usize> = HashTable::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            counts.entry(word).and_modify(|n| *n += 1).or_insert(1);
+        }
+        assert_eq!(counts.get("the"), Some(&3));
+        assert_eq!(counts.get("and"), Some(&2));
+        assert_eq!(counts.get("cat"), Some(&1));
+
+        *counts.entry("dog").or_default() += 5;
+        assert_eq!(counts.get("dog"), Some(&5));
+
+        let mut calls = 0;
+        counts.entry("dog").or_insert_with(|| {
+            calls += 1;
+            0
+        });
+        assert_eq!(calls, 0);
+        assert_eq!(*counts.entry("mouse").or_insert_with_key(|k| k.len()), 5);
+
+        match counts.entry("the") {
+            Entry::Occupied(e) => {
+                assert_eq!(e.key(), &"the");
+                assert_eq!(e.remove(), 3);
+            }
+            Entry::Vacant(_) => panic!("the was counted"),
+        }
+        assert_eq!(counts.get("the"), None);
+
+        match counts.entry("fox") {
+            Entry::Vacant(e) => {
+                assert_eq!(e.key(), &"fox");
+                *e.insert(1) += 1;
+            }
+            Entry::Occupied(_) => panic!("fox was never counted"),
+        }
+        assert_eq!(counts.get("fox"), Some(&2));
+        assert_eq!(counts.entry("fox").key(), &"fox");
+    }
+
     #[test]
     fn test_insert_with_same_key() {
         let mut hash_table = HashTable::new();
@@ -566,6 +2445,282 @@ mod tests {
         assert_eq!(old_user.age, 27);
     }
 
+    #[test]
+    fn test_insert_then_get() {
+        let mut hash_table = HashTable::new();
+
+        let (user, old_user) = hash_table.insert_then_get(
+            "gedalia",
+            User {
+                name: "gedalia".to_string(),
+                age: 27,
+            },
+        );
+        assert!(old_user.is_none());
+        user.age += 100;
+
+        let (user, old_user) = hash_table.insert_then_get(
+            "gedalia",
+            User {
+                name: "gedalia".to_string(),
+                age: 0,
+            },
+        );
+        assert_eq!(old_user.unwrap().age, 127);
+        user.age += 1;
+
+        assert_eq!(hash_table.get(&"gedalia").unwrap().age, 1);
+    }
+
+    #[test]
+    fn test_extend_with_merges_duplicates() {
+        let mut word_counts = HashTable::new();
+        word_counts.insert("the", 10);
+
+        let words = "the cat and the hat and the bat".split(' ').map(|w| (w, 1));
+        word_counts.extend_with(words, |_, count, one| *count += one);
+
+        assert_eq!(word_counts.get(&"the"), Some(&13));
+        assert_eq!(word_counts.get(&"and"), Some(&2));
+        assert_eq!(word_counts.get(&"cat"), Some(&1));
+        assert_eq!(word_counts.len(), 5);
+    }
+
+    #[test]
+    fn test_iter_buckets() {
+        let mut hash_table = HashTable::with_capacity(4);
+        hash_table.insert(1, 10);
+        hash_table.insert(2, 20);
+        hash_table.insert(3, 30);
+
+        assert_eq!(hash_table.iter_buckets().count(), hash_table.capacity());
+        let mut entries: Vec<(i32, i32)> = hash_table
+            .iter_buckets()
+            .flat_map(|bucket| bucket.copied())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec![(1, 10), (2, 20), (3, 30)]);
+
+        for bucket in hash_table.iter_buckets_mut() {
+            for (k, v) in bucket {
+                *v += k;
+            }
+        }
+        assert_eq!(hash_table.get(&1), Some(&11));
+        assert_eq!(hash_table.get(&2), Some(&22));
+        assert_eq!(hash_table.get(&3), Some(&33));
+    }
+
+    #[test]
+    fn test_get_cloned() {
+        let mut hash_table = HashTable::new();
+        hash_table.insert("gedalia", String::from("kott"));
+
+        let surname = hash_table.get_cloned(&"gedalia");
+        // the table can be mutated while the copy is still around
+        hash_table.remove(&"gedalia");
+        assert_eq!(surname, Some(String::from("kott")));
+        assert_eq!(hash_table.get_cloned(&"gedalia"), None);
+    }
+
+    #[test]
+    fn test_swap_values() {
+        // every key shares the zeroth bucket, so swapping within one bucket gets covered too
+        struct SillyHasher;
+        impl<K> SimpleHasher<K> for SillyHasher
+        where
+            K: Hash,
+        {
+            fn hash(&self, _: &K) -> u64 {
+                0
+            }
+        }
+
+        let mut hash_table = HashTable::new();
+        let mut collided = HashTable::with_hasher(SillyHasher {});
+        for (name, age) in [("gedalia", 27), ("theo", 0), ("aviva", 26)].iter() {
+            hash_table.insert(*name, *age);
+            collided.insert(*name, *age);
+        }
+
+        assert_eq!(hash_table.swap_values(&"gedalia", &"theo"), Ok(()));
+        assert_eq!(hash_table.get(&"gedalia"), Some(&0));
+        assert_eq!(hash_table.get(&"theo"), Some(&27));
+        assert_eq!(collided.swap_values(&"theo", &"aviva"), Ok(()));
+        assert_eq!(collided.get(&"theo"), Some(&26));
+        assert_eq!(collided.get(&"aviva"), Some(&0));
+
+        assert_eq!(hash_table.swap_values(&"aviva", &"aviva"), Ok(()));
+        assert_eq!(hash_table.get(&"aviva"), Some(&26));
+        assert_eq!(
+            hash_table.swap_values(&"nowhereman", &"aviva"),
+            Err(SwapValuesError::FirstMissing)
+        );
+        assert_eq!(
+            hash_table.swap_values(&"aviva", &"nowhereman"),
+            Err(SwapValuesError::SecondMissing)
+        );
+        assert_eq!(hash_table.get(&"aviva"), Some(&26));
+    }
+
+    #[test]
+    fn test_replace_swaps_key_and_value() {
+        // keys that compare equal while carrying different metadata
+        #[derive(Debug, Clone)]
+        struct Tagged(&'static str, i32);
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Hash for Tagged {
+            fn hash<S: Hasher>(&self, state: &mut S) {
+                self.0.hash(state);
+            }
+        }
+
+        let mut hash_table = HashTable::new();
+        assert!(hash_table.replace(Tagged("gedalia", 1), 27).is_none());
+
+        let (old_k, old_v) = hash_table.replace(Tagged("gedalia", 2), 28).unwrap();
+        assert_eq!(old_k.1, 1);
+        assert_eq!(old_v, 27);
+
+        // the new key is the one stored now
+        let (k, v) = (&hash_table).into_iter().next().unwrap();
+        assert_eq!(k.1, 2);
+        assert_eq!(*v, 28);
+    }
+
+    #[test]
+    fn test_keys_eq_and_is_disjoint() {
+        let mut ages = HashTable::new();
+        ages.insert("gedalia", 27);
+        ages.insert("theo", 0);
+
+        let mut names = HashTable::with_capacity(3);
+        names.insert("theo", "theo".to_string());
+        names.insert("gedalia", "gedalia".to_string());
+
+        assert!(ages.keys_eq(&names));
+        assert!(!ages.is_disjoint(&names));
+
+        names.insert("aviva", "aviva".to_string());
+        assert!(!ages.keys_eq(&names));
+        assert!(!names.keys_eq(&ages));
+
+        let mut others = HashTable::new();
+        others.insert("chani", ());
+        others.insert("nachmi", ());
+        others.insert("avery", ());
+        assert!(ages.is_disjoint(&others));
+        assert!(others.is_disjoint(&ages));
+        assert!(names.is_disjoint(&HashTable::<&str, ()>::new()));
+    }
+
+    #[test]
+    fn test_raw_parts_round_trip() {
+        let mut hash_table = HashTable::new();
+        for k in 0..100 {
+            hash_table.insert(k, k * 2);
+        }
+
+        let (buckets, total_entries, hasher) = hash_table.into_raw_parts();
+        assert_eq!(total_entries, 100);
+        assert_eq!(buckets.iter().map(|b| b.len()).sum::<usize>(), 100);
+
+        // the parts came straight out of a table with the same hasher
+        let mut hash_table = unsafe { HashTable::from_raw_parts(buckets, total_entries, hasher) };
+        for k in 0..100 {
+            assert_eq!(hash_table.get(&k), Some(&(k * 2)));
+        }
+        hash_table.insert(100, 200);
+        assert_eq!(hash_table.remove(&0), Some(0));
+        assert_eq!(hash_table.get(&100), Some(&200));
+    }
+
+    #[test]
+    fn test_borrowed_keys_and_values() {
+        fn parse(config: &str) -> HashTable<&str, &str> {
+            HashTable::from_pairs_borrowed(config.lines().filter_map(|line| {
+                let mut parts = line.splitn(2, '=');
+                Some((parts.next()?.trim(), parts.next()?.trim()))
+            }))
+        }
+
+        let config = String::from("name = gedalia\nage = 27\ncity = nyc\nage = 28\n");
+        let mut hash_table = parse(&config);
+        assert_eq!(hash_table.get(&"name"), Some(&"gedalia"));
+        assert_eq!(hash_table.get(&"age"), Some(&"28"));
+
+        // lookups work with keys borrowed from somewhere else entirely
+        let lookup = String::from("city");
+        assert_eq!(hash_table.get(&lookup.as_str()), Some(&"nyc"));
+
+        hash_table.entry("country").or_insert("usa");
+        let mut keys: Vec<&str> = hash_table.into_keys().into_iter().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["age", "city", "country", "name"]);
+        // every key pointed into the buffer, apart from the one added by hand
+        assert!(keys
+            .iter()
+            .filter(|k| **k != "country")
+            .all(|k| config.as_bytes().as_ptr_range().contains(&k.as_ptr())));
+    }
+
+    #[test]
+    fn test_try_from_iter() {
+        let hash_table = HashTable::try_from_iter(vec![("gedalia", 27), ("theo", 0)]).unwrap();
+        assert_eq!(hash_table.get(&"gedalia"), Some(&27));
+        assert_eq!(hash_table.get(&"theo"), Some(&0));
+
+        let error = HashTable::try_from_iter(vec![("gedalia", 27), ("theo", 0), ("gedalia", 28)])
+            .err()
+            .unwrap();
+        assert_eq!(error.key(), &"gedalia");
+        assert_eq!(error.to_string(), "duplicate key \"gedalia\"");
+    }
+
+    #[test]
+    fn test_from_keys_with() {
+        let mut calls = 0;
+        let hash_table = HashTable::from_keys_with(vec!["gedalia", "theo", "gedalia"], |k| {
+            calls += 1;
+            k.len()
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(hash_table.len(), 2);
+        assert_eq!(hash_table.get(&"gedalia"), Some(&7));
+        assert_eq!(hash_table.get(&"theo"), Some(&4));
+
+        // sized from the size hint so building never has to grow
+        let hash_table = HashTable::from_unique_keys_with(0..100, |k| k * 2);
+        assert_eq!(hash_table.capacity(), 134);
+        for k in 0..100 {
+            assert_eq!(hash_table.get(&k), Some(&(k * 2)));
+        }
+    }
+
+    #[test]
+    fn test_into_shards() {
+        let mut hash_table = HashTable::new();
+        for k in 0..1000 {
+            hash_table.insert(k, k * 2);
+        }
+
+        let shards = hash_table.into_shards(4);
+        assert_eq!(shards.len(), 4);
+        assert_eq!(shards.iter().map(|s| s.len()).sum::<usize>(), 1000);
+        for k in 0..1000 {
+            let holders: Vec<_> = shards.iter().filter(|s| s.get(&k).is_some()).collect();
+            assert_eq!(holders.len(), 1);
+            assert_eq!(holders[0].get(&k), Some(&(k * 2)));
+        }
+        for shard in &shards {
+            assert!(!shard.is_empty());
+        }
+    }
+
     #[test]
     fn test_into_keys() {
         let mut hash_table = HashTable::with_capacity(9);
@@ -610,7 +2765,7 @@ mod tests {
         let keys = hash_table.into_keys();
 
         for k in &keys {
-            let found = users.binary_search_by(|u| u.name.cmp(&k));
+            let found = users.binary_search_by(|u| u.name.cmp(k));
             assert!(found.is_ok());
         }
 
@@ -0,0 +1,356 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::{DefaultSimpleHasher, SimpleHasher, MAX_LOAD_FACTOR};
+
+// buckets start few and grow with the entries, so a generous bound costs nothing up front
+const INITIAL_BUCKETS: usize = 10;
+
+struct Node<K, V> {
+    k: K,
+    v: V,
+    // neighbours in recency order, `prev` being more recently used
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// A bounded hash table that evicts its least recently used entry once it is full.
+///
+/// Entries live in a slab of nodes threaded into a doubly linked recency list, and hash buckets hold
+/// slab positions, so lookups, promotion and eviction are all O(1).
+pub struct LruHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    buckets: Vec<Vec<usize>>,
+    most_recent: Option<usize>,
+    least_recent: Option<usize>,
+    len: usize,
+    max_entries: usize,
+    hasher: H,
+}
+
+impl<K, V> LruHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new(max_entries: usize) -> LruHashTable<K, V, DefaultSimpleHasher> {
+        LruHashTable::with_hasher(max_entries, DefaultSimpleHasher::new())
+    }
+}
+
+impl<K, V, H> LruHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    /// Panics if `max_entries` is zero.
+    pub fn with_hasher(max_entries: usize, hasher: H) -> LruHashTable<K, V, H> {
+        assert!(max_entries > 0, "an LRU table must hold at least one entry");
+        LruHashTable {
+            nodes: vec![],
+            free: vec![],
+            buckets: vec![vec![]; INITIAL_BUCKETS],
+            most_recent: None,
+            least_recent: None,
+            len: 0,
+            max_entries,
+            hasher,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `k` as the most recently used entry, evicting the least recently used one if the table is full.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let hash = self.hasher.hash(&k);
+        if let Some(index) = self.find(hash, &k) {
+            self.unlink(index);
+            self.push_front(index);
+            return Some(core::mem::replace(&mut self.node_mut(index).v, v));
+        }
+
+        if self.len == self.max_entries {
+            self.pop_lru();
+        } else if (self.len + 1) as f64 / self.buckets.len() as f64 > MAX_LOAD_FACTOR {
+            self.resize(self.buckets.len().saturating_mul(2));
+        }
+
+        let node = Node {
+            k,
+            v,
+            prev: None,
+            next: None,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        let bucket_index = hash as usize % self.buckets.len();
+        self.buckets[bucket_index].push(index);
+        self.push_front(index);
+        self.len += 1;
+        None
+    }
+
+    /// Looks up `k` and marks it as the most recently used entry.
+    pub fn get(&mut self, k: &K) -> Option<&V> {
+        let index = self.find(self.hasher.hash(k), k)?;
+        self.unlink(index);
+        self.push_front(index);
+        Some(&self.node(index).v)
+    }
+
+    /// Looks up `k` without touching its recency.
+    pub fn peek(&self, k: &K) -> Option<&V> {
+        let index = self.find(self.hasher.hash(k), k)?;
+        Some(&self.node(index).v)
+    }
+
+    /// Marks `k` as the most recently used entry. Returns whether it was present.
+    pub fn promote(&mut self, k: &K) -> bool {
+        match self.find(self.hasher.hash(k), k) {
+            Some(index) => {
+                self.unlink(index);
+                self.push_front(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Marks `k` as the least recently used entry, making it the next to be evicted. Returns whether it was present.
+    pub fn demote(&mut self, k: &K) -> bool {
+        match self.find(self.hasher.hash(k), k) {
+            Some(index) => {
+                self.unlink(index);
+                self.push_back(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let index = self.find(self.hasher.hash(k), k)?;
+        let (_, v) = self.take(index);
+        Some(v)
+    }
+
+    /// Removes and returns the least recently used entry.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let index = self.least_recent?;
+        Some(self.take(index))
+    }
+
+    /// Iterates entries from most to least recently used without changing their recency.
+    pub fn iter(&self) -> LruIter<'_, K, V> {
+        LruIter {
+            nodes: &self.nodes,
+            next: self.most_recent,
+        }
+    }
+
+    fn find(&self, hash: u64, k: &K) -> Option<usize> {
+        let bucket_index = hash as usize % self.buckets.len();
+        self.buckets[bucket_index]
+            .iter()
+            .copied()
+            .find(|&index| self.hasher.key_eq(&self.node(index).k, k))
+    }
+
+    fn resize(&mut self, number_of_buckets: usize) {
+        let mut buckets: Vec<Vec<usize>> = vec![vec![]; number_of_buckets];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(node) = node {
+                buckets[self.hasher.hash(&node.k) as usize % number_of_buckets].push(index);
+            }
+        }
+        self.buckets = buckets;
+    }
+
+    fn node(&self, index: usize) -> &Node<K, V> {
+        self.nodes[index]
+            .as_ref()
+            .expect("linked nodes are occupied")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<K, V> {
+        self.nodes[index]
+            .as_mut()
+            .expect("linked nodes are occupied")
+    }
+
+    fn take(&mut self, index: usize) -> (K, V) {
+        self.unlink(index);
+        let node = self.nodes[index].take().expect("linked nodes are occupied");
+        let bucket_index = self.hasher.hash(&node.k) as usize % self.buckets.len();
+        let bucket = &mut self.buckets[bucket_index];
+        let slot = bucket
+            .iter()
+            .position(|&i| i == index)
+            .expect("every node is indexed by its hash bucket");
+        bucket.swap_remove(slot);
+        self.free.push(index);
+        self.len -= 1;
+        (node.k, node.v)
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = {
+            let node = self.node(index);
+            (node.prev, node.next)
+        };
+        match prev {
+            Some(prev) => self.node_mut(prev).next = next,
+            None => self.most_recent = next,
+        }
+        match next {
+            Some(next) => self.node_mut(next).prev = prev,
+            None => self.least_recent = prev,
+        }
+    }
+
+    fn push_front(&mut self, index: usize) {
+        let old_most_recent = self.most_recent;
+        {
+            let node = self.node_mut(index);
+            node.prev = None;
+            node.next = old_most_recent;
+        }
+        match old_most_recent {
+            Some(old) => self.node_mut(old).prev = Some(index),
+            None => self.least_recent = Some(index),
+        }
+        self.most_recent = Some(index);
+    }
+
+    fn push_back(&mut self, index: usize) {
+        let old_least_recent = self.least_recent;
+        {
+            let node = self.node_mut(index);
+            node.prev = old_least_recent;
+            node.next = None;
+        }
+        match old_least_recent {
+            Some(old) => self.node_mut(old).next = Some(index),
+            None => self.most_recent = Some(index),
+        }
+        self.least_recent = Some(index);
+    }
+}
+
+pub struct LruIter<'a, K, V> {
+    nodes: &'a [Option<Node<K, V>>],
+    next: Option<usize>,
+}
+
+impl<'a, K, V> Iterator for LruIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.nodes[self.next?]
+            .as_ref()
+            .expect("linked nodes are occupied");
+        self.next = node.next;
+        Some((&node.k, &node.v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LruHashTable;
+
+    fn recency(hash_table: &LruHashTable<&'static str, i32>) -> Vec<&'static str> {
+        hash_table.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn test_eviction_and_recency_order() {
+        let mut hash_table = LruHashTable::new(3);
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("theo", 0);
+        hash_table.insert("aviva", 26);
+        assert_eq!(recency(&hash_table), vec!["aviva", "theo", "gedalia"]);
+
+        // reading promotes, so theo is now the least recently used
+        assert_eq!(hash_table.get(&"gedalia"), Some(&27));
+        assert_eq!(recency(&hash_table), vec!["gedalia", "aviva", "theo"]);
+
+        hash_table.insert("chani", 25);
+        assert_eq!(hash_table.len(), 3);
+        assert_eq!(hash_table.peek(&"theo"), None);
+        assert_eq!(recency(&hash_table), vec!["chani", "gedalia", "aviva"]);
+    }
+
+    #[test]
+    fn test_peek_promote_demote_and_pop_lru() {
+        let mut hash_table = LruHashTable::new(4);
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("theo", 0);
+        hash_table.insert("aviva", 26);
+
+        // peeking leaves the order alone
+        assert_eq!(hash_table.peek(&"gedalia"), Some(&27));
+        assert_eq!(recency(&hash_table), vec!["aviva", "theo", "gedalia"]);
+
+        assert!(hash_table.promote(&"gedalia"));
+        assert!(hash_table.demote(&"aviva"));
+        assert!(!hash_table.promote(&"nowhereman"));
+        assert_eq!(recency(&hash_table), vec!["gedalia", "theo", "aviva"]);
+
+        assert_eq!(hash_table.pop_lru(), Some(("aviva", 26)));
+        assert_eq!(hash_table.remove(&"gedalia"), Some(27));
+        assert_eq!(recency(&hash_table), vec!["theo"]);
+
+        // freed slots get reused
+        hash_table.insert("chani", 25);
+        hash_table.insert("nachmi", 24);
+        assert_eq!(recency(&hash_table), vec!["nachmi", "chani", "theo"]);
+        assert_eq!(hash_table.pop_lru(), Some(("theo", 0)));
+        assert_eq!(hash_table.pop_lru(), Some(("chani", 25)));
+        assert_eq!(hash_table.pop_lru(), Some(("nachmi", 24)));
+        assert_eq!(hash_table.pop_lru(), None);
+        assert!(hash_table.is_empty());
+    }
+
+    #[test]
+    fn test_buckets_grow_with_entries() {
+        // a bound this large used to overflow working out the bucket count
+        let mut hash_table = LruHashTable::new(usize::MAX);
+        assert_eq!(hash_table.buckets.len(), 10);
+        for k in 0..1000 {
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.buckets.len(), 2560);
+        for k in 0..1000 {
+            assert_eq!(hash_table.peek(&k), Some(&k));
+        }
+
+        // once full it stops growing and evicts instead
+        let mut hash_table = LruHashTable::new(100);
+        for k in 0..1000 {
+            hash_table.insert(k, k);
+        }
+        assert_eq!(hash_table.buckets.len(), 160);
+        assert_eq!(hash_table.len(), 100);
+        assert_eq!(hash_table.peek(&899), None);
+        assert_eq!(hash_table.peek(&900), Some(&900));
+    }
+}
@@ -0,0 +1,208 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::{DefaultSimpleHasher, Entry, HashTable, OccupiedEntry, SimpleHasher, VacantEntry};
+
+/// A table mapping each key to every value inserted under it, in insertion order.
+/// Keys are only present while they have at least one value.
+pub struct HashMultiTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, Vec<V>, H>,
+}
+
+impl<K, V> Default for HashMultiTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        HashMultiTable {
+            inner: Default::default(),
+        }
+    }
+}
+
+impl<K, V> HashMultiTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> HashMultiTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> HashMultiTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> HashMultiTable<K, V, H> {
+        HashMultiTable {
+            inner: HashTable::with_hasher(hasher),
+        }
+    }
+
+    /// Appends `v` to the values stored under `k`.
+    pub fn insert(&mut self, k: K, v: V) {
+        self.entry(k).push(v);
+    }
+
+    pub fn get(&self, k: &K) -> Option<&[V]> {
+        self.inner.get(k).map(|vs| vs.as_slice())
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut [V]> {
+        self.inner.get_mut(k).map(|vs| vs.as_mut_slice())
+    }
+
+    /// Removes the key along with all of its values.
+    pub fn remove(&mut self, k: &K) -> Option<Vec<V>> {
+        self.inner.remove(k)
+    }
+
+    pub fn entry(&mut self, k: K) -> MultiEntry<'_, K, V, H> {
+        match self.inner.entry(k) {
+            Entry::Occupied(inner) => MultiEntry::Occupied(OccupiedMultiEntry { inner }),
+            Entry::Vacant(inner) => MultiEntry::Vacant(VacantMultiEntry { inner }),
+        }
+    }
+}
+
+pub enum MultiEntry<'a, K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    Occupied(OccupiedMultiEntry<'a, K, V, H>),
+    Vacant(VacantMultiEntry<'a, K, V, H>),
+}
+
+impl<'a, K, V, H> MultiEntry<'a, K, V, H>
+where
+    K: PartialEq + Hash,
+    H: SimpleHasher<K>,
+{
+    /// Appends `v` to the key's values, creating the key if this is its first value.
+    pub fn push(self, v: V) -> OccupiedMultiEntry<'a, K, V, H> {
+        match self {
+            MultiEntry::Occupied(mut e) => {
+                e.push(v);
+                e
+            }
+            MultiEntry::Vacant(e) => e.push(v),
+        }
+    }
+}
+
+pub struct OccupiedMultiEntry<'a, K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    inner: OccupiedEntry<'a, K, Vec<V>, H>,
+}
+
+impl<'a, K, V, H> OccupiedMultiEntry<'a, K, V, H>
+where
+    K: PartialEq + Hash,
+    H: SimpleHasher<K>,
+{
+    pub fn values(&self) -> &[V] {
+        self.inner.get()
+    }
+
+    pub fn values_mut(&mut self) -> &mut [V] {
+        self.inner.get_mut()
+    }
+
+    pub fn into_values_mut(self) -> &'a mut [V] {
+        self.inner.into_mut()
+    }
+
+    pub fn push(&mut self, v: V) {
+        self.inner.get_mut().push(v);
+    }
+}
+
+pub struct VacantMultiEntry<'a, K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    inner: VacantEntry<'a, K, Vec<V>, H>,
+}
+
+impl<'a, K, V, H> VacantMultiEntry<'a, K, V, H>
+where
+    K: PartialEq + Hash,
+    H: SimpleHasher<K>,
+{
+    pub fn into_key(self) -> K {
+        self.inner.into_key()
+    }
+
+    pub fn push(self, v: V) -> OccupiedMultiEntry<'a, K, V, H> {
+        OccupiedMultiEntry {
+            inner: self.inner.insert_entry(vec![v]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HashMultiTable, MultiEntry};
+
+    #[test]
+    fn test_grouped_accumulation() {
+        let words = [
+            "apple",
+            "avocado",
+            "banana",
+            "blueberry",
+            "cherry",
+            "apricot",
+        ];
+
+        let mut by_letter = HashMultiTable::new();
+        for word in &words {
+            by_letter.entry(word.chars().next().unwrap()).push(*word);
+        }
+
+        assert_eq!(
+            by_letter.get(&'a'),
+            Some(&["apple", "avocado", "apricot"][..])
+        );
+        assert_eq!(by_letter.get(&'b'), Some(&["banana", "blueberry"][..]));
+        assert_eq!(by_letter.get(&'c'), Some(&["cherry"][..]));
+        assert_eq!(by_letter.get(&'d'), None);
+    }
+
+    #[test]
+    fn test_multi_entry_values_mut() {
+        let mut hash_table = HashMultiTable::new();
+        hash_table.insert("gedalia", 27);
+        hash_table.insert("gedalia", 28);
+
+        match hash_table.entry("gedalia") {
+            MultiEntry::Occupied(mut e) => {
+                for v in e.values_mut() {
+                    *v += 100;
+                }
+                e.push(0);
+            }
+            MultiEntry::Vacant(_) => panic!("gedalia has values"),
+        }
+
+        assert_eq!(hash_table.get(&"gedalia"), Some(&[127, 128, 0][..]));
+        assert_eq!(hash_table.remove(&"gedalia"), Some(vec![127, 128, 0]));
+        assert_eq!(hash_table.get(&"gedalia"), None);
+
+        match hash_table.entry("theo") {
+            MultiEntry::Vacant(e) => assert_eq!(e.into_key(), "theo"),
+            MultiEntry::Occupied(_) => panic!("theo was never inserted"),
+        }
+    }
+}
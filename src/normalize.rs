@@ -0,0 +1,123 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::{HashTable, SimpleHasher};
+
+/// A normalization applied to keys before they are hashed or compared, so keys that normalize to the
+/// same thing address the same entry.
+///
+/// Implementations must keep the two methods consistent: keys that are `eq_normalized` must feed
+/// identical data to `hash_normalized`.
+pub trait KeyNormalize<K: ?Sized> {
+    fn hash_normalized<S: Hasher>(&self, k: &K, state: &mut S);
+
+    fn eq_normalized(&self, a: &K, b: &K) -> bool;
+}
+
+/// Treats string keys as equal when they only differ in ASCII case, as HTTP header names do.
+pub struct AsciiCaseInsensitive;
+
+impl<K> KeyNormalize<K> for AsciiCaseInsensitive
+where
+    K: AsRef<str> + ?Sized,
+{
+    fn hash_normalized<S: Hasher>(&self, k: &K, state: &mut S) {
+        for b in k.as_ref().bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        // terminate like str's own Hash impl so adjacent keys can't run together
+        state.write_u8(0xff);
+    }
+
+    fn eq_normalized(&self, a: &K, b: &K) -> bool {
+        a.as_ref().eq_ignore_ascii_case(b.as_ref())
+    }
+}
+
+/// A `SimpleHasher` that hashes and compares keys through a `KeyNormalize`. Normalized keys are
+/// hashed with SipHash keyed by a random seed per hasher, as `DefaultSimpleHasher` does.
+pub struct NormalizedHasher<N> {
+    normalizer: N,
+    state: RandomState,
+}
+
+impl<N> NormalizedHasher<N> {
+    pub fn new(normalizer: N) -> Self {
+        NormalizedHasher {
+            normalizer,
+            state: RandomState::new(),
+        }
+    }
+}
+
+impl<K, N> SimpleHasher<K> for NormalizedHasher<N>
+where
+    K: Hash + ?Sized,
+    N: KeyNormalize<K>,
+{
+    fn hash(&self, t: &K) -> u64 {
+        let mut s = self.state.build_hasher();
+        self.normalizer.hash_normalized(t, &mut s);
+        s.finish()
+    }
+
+    fn key_eq(&self, a: &K, b: &K) -> bool
+    where
+        K: PartialEq,
+    {
+        self.normalizer.eq_normalized(a, b)
+    }
+}
+
+impl<K, V, N> HashTable<K, V, NormalizedHasher<N>>
+where
+    K: Hash + PartialEq,
+    N: KeyNormalize<K>,
+{
+    /// A table whose keys are hashed and compared in their normalized form. Keys are stored as first inserted.
+    pub fn with_normalizer(normalizer: N) -> HashTable<K, V, NormalizedHasher<N>> {
+        HashTable::with_hasher(NormalizedHasher::new(normalizer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AsciiCaseInsensitive, HashTable, NormalizedHasher, SimpleHasher};
+
+    #[test]
+    fn test_case_insensitive_keys() {
+        let mut headers = HashTable::with_normalizer(AsciiCaseInsensitive);
+        headers.insert("Content-Type", "text/html");
+        headers.insert("Content-Length", "42");
+
+        assert_eq!(headers.get(&"content-type"), Some(&"text/html"));
+        assert_eq!(headers.get(&"CONTENT-LENGTH"), Some(&"42"));
+        assert_eq!(headers.get(&"content-encoding"), None);
+
+        assert_eq!(
+            headers.insert("content-type", "text/plain"),
+            Some("text/html")
+        );
+        assert_eq!(headers.get(&"Content-Type"), Some(&"text/plain"));
+        assert_eq!(headers.remove(&"CONTENT-TYPE"), Some("text/plain"));
+        assert_eq!(headers.get(&"content-type"), None);
+    }
+
+    #[test]
+    fn test_owned_string_keys() {
+        let mut headers = HashTable::with_normalizer(AsciiCaseInsensitive);
+        headers.insert(String::from("Accept"), 1);
+
+        assert_eq!(headers.get(&String::from("accept")), Some(&1));
+        assert_eq!(headers.get("ACCEPT"), Some(&1));
+        assert_eq!(headers.remove("accept"), Some(1));
+    }
+
+    #[test]
+    fn test_hasher_is_seeded_per_instance() {
+        let a = NormalizedHasher::new(AsciiCaseInsensitive);
+        let b = NormalizedHasher::new(AsciiCaseInsensitive);
+        assert_ne!(a.hash("Accept"), b.hash("Accept"));
+        assert_eq!(a.hash("Accept"), a.hash("ACCEPT"));
+    }
+}
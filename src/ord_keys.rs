@@ -0,0 +1,155 @@
+use core::cmp::Ordering;
+use core::hash::Hash;
+
+use crate::{HashTable, SimpleHasher};
+
+/// Wraps a `SimpleHasher` for keys that are `Ord`, so tables can also order long chains by key and
+/// binary search keys that share a full hash. Without it such keys are compared one at a time, which
+/// anyone able to pick colliding keys can use to make every lookup and removal linear. The seeded
+/// `DefaultSimpleHasher` keeps anyone from picking them, so this matters most for unseeded hashers such
+/// as `FnvSimpleHasher`, or the default one without the `std` feature.
+///
+/// Keys are compared with `==` and `Ord`, whatever the wrapped hasher's `key_eq` does.
+#[derive(Clone, Debug, Default)]
+pub struct OrdKeyHasher<H> {
+    hasher: H,
+}
+
+impl<H> OrdKeyHasher<H> {
+    pub fn new(hasher: H) -> OrdKeyHasher<H> {
+        OrdKeyHasher { hasher }
+    }
+}
+
+impl<K, H> SimpleHasher<K> for OrdKeyHasher<H>
+where
+    K: Hash + Ord + ?Sized,
+    H: SimpleHasher<K>,
+{
+    fn hash(&self, t: &K) -> u64 {
+        self.hasher.hash(t)
+    }
+
+    fn key_cmp(&self, a: &K, b: &K) -> Option<Ordering> {
+        Some(a.cmp(b))
+    }
+}
+
+impl<K, V, H> HashTable<K, V, OrdKeyHasher<H>>
+where
+    K: Hash + Ord,
+    H: SimpleHasher<K>,
+{
+    /// A table hashing its keys with `hasher` that binary searches keys sharing a hash by their order.
+    pub fn with_ordered_keys(hasher: H) -> HashTable<K, V, OrdKeyHasher<H>> {
+        HashTable::with_hasher(OrdKeyHasher::new(hasher))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use core::cell::Cell;
+    use core::cmp::Ordering;
+    use core::hash::{Hash, Hasher};
+
+    use crate::{HashTable, SimpleHasher};
+
+    struct ConstantHasher;
+    impl SimpleHasher<str> for ConstantHasher {
+        fn hash(&self, _: &str) -> u64 {
+            0
+        }
+    }
+    impl SimpleHasher<String> for ConstantHasher {
+        fn hash(&self, _: &String) -> u64 {
+            0
+        }
+    }
+    impl SimpleHasher<Counted<'_>> for ConstantHasher {
+        fn hash(&self, _: &Counted<'_>) -> u64 {
+            0
+        }
+    }
+
+    // a key that counts how often it's ordered against another
+    struct Counted<'a> {
+        k: u32,
+        comparisons: &'a Cell<usize>,
+    }
+    impl PartialEq for Counted<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.k == other.k
+        }
+    }
+    impl Eq for Counted<'_> {}
+    impl PartialOrd for Counted<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Counted<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.comparisons.set(self.comparisons.get() + 1);
+            self.k.cmp(&other.k)
+        }
+    }
+    impl Hash for Counted<'_> {
+        fn hash<S: Hasher>(&self, state: &mut S) {
+            self.k.hash(state);
+        }
+    }
+
+    #[test]
+    fn test_colliding_keys_are_binary_searched() {
+        let mut hash_table = HashTable::with_ordered_keys(ConstantHasher);
+        for k in (0..1000).rev() {
+            hash_table.insert(k.to_string(), k);
+        }
+
+        // every key shares a hash and so a bucket, yet a probe compares about log2(1000) of them
+        for k in 0..1000 {
+            let (found, probe_length) = hash_table.probe(0, k.to_string().as_str());
+            assert!(found.is_some());
+            assert!(probe_length <= 10, "compared {} keys", probe_length);
+            assert_eq!(hash_table.get(k.to_string().as_str()), Some(&k));
+        }
+        let (found, probe_length) = hash_table.probe(0, "missing");
+        assert_eq!(found, None);
+        assert!(probe_length <= 10, "compared {} keys", probe_length);
+
+        for k in (0..1000).step_by(2) {
+            assert_eq!(hash_table.remove(k.to_string().as_str()), Some(k));
+        }
+        for k in 0..1000 {
+            let expected = if k % 2 == 0 { None } else { Some(&k) };
+            assert_eq!(hash_table.get(k.to_string().as_str()), expected);
+        }
+    }
+
+    #[test]
+    fn test_removing_colliding_keys_is_binary_searched() {
+        let comparisons = Cell::new(0);
+        let key = |k| Counted {
+            k,
+            comparisons: &comparisons,
+        };
+        let mut hash_table = HashTable::with_ordered_keys(ConstantHasher);
+        for k in 0..1000 {
+            hash_table.insert(key(k), k);
+        }
+
+        // a removal finds the key, then where the chain holds it and the entry moving into its place,
+        // and each of those compares at most 11 of the 1000 keys however long the chain
+        for k in 0..1000 {
+            comparisons.set(0);
+            assert_eq!(hash_table.remove(&key(k)), Some(k));
+            assert!(
+                comparisons.get() <= 3 * 11,
+                "compared {} keys",
+                comparisons.get()
+            );
+        }
+        assert!(hash_table.is_empty());
+    }
+}
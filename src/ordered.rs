@@ -0,0 +1,323 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::hash_index::HashIndex;
+use crate::{key_order, DefaultSimpleHasher, SimpleHasher, MAX_LOAD_FACTOR};
+
+/// A hash table that remembers the order its entries were inserted in.
+///
+/// Entries live in a dense vector in insertion order, found through the same hash index `HashTable` uses.
+/// There is no plain `remove`: `swap_remove` is O(1) but moves the last entry into the gap, while
+/// `shift_remove` keeps the order of the rest at O(n).
+pub struct OrderedHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    entries: Vec<(K, V)>,
+    index: HashIndex,
+    hasher: H,
+}
+
+impl<K, V> Default for OrderedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        OrderedHashTable::from_parts(10, DefaultSimpleHasher::new())
+    }
+}
+
+impl<K, V> OrderedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> OrderedHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> OrderedHashTable<K, V, H>
+where
+    K: Hash,
+    H: SimpleHasher<K>,
+{
+    fn from_parts(number_of_buckets: usize, hasher: H) -> OrderedHashTable<K, V, H> {
+        OrderedHashTable {
+            entries: vec![],
+            index: HashIndex::new(number_of_buckets),
+            hasher,
+        }
+    }
+}
+
+impl<K, V, H> OrderedHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> OrderedHashTable<K, V, H> {
+        OrderedHashTable::from_parts(10, hasher)
+    }
+
+    /// Inserts at the end of the order. Replacing the value of an existing key keeps its position.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let hash = self.hasher.hash(&k);
+        if let Some(index) = self.find(hash, &k) {
+            let (_, ev) = &mut self.entries[index];
+            return Some(core::mem::replace(ev, v));
+        }
+
+        let number_of_buckets = self.index.number_of_buckets();
+        let new_load_factor = (self.entries.len() + 1) as f64 / number_of_buckets as f64;
+        if new_load_factor > MAX_LOAD_FACTOR {
+            self.index.resize(
+                number_of_buckets * 2,
+                key_order(&self.entries, &self.hasher),
+            );
+        }
+
+        self.entries.push((k, v));
+        self.index
+            .push(hash, key_order(&self.entries, &self.hasher));
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let index = self.get_index_of(k)?;
+        let (_, v) = &self.entries[index];
+        Some(v)
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let index = self.get_index_of(k)?;
+        let (_, v) = &mut self.entries[index];
+        Some(v)
+    }
+
+    /// The position of `k` in the table's order.
+    pub fn get_index_of(&self, k: &K) -> Option<usize> {
+        self.find(self.hasher.hash(k), k)
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|(k, v)| (k, v))
+    }
+
+    /// Iterates entries in the table's order.
+    pub fn iter(&self) -> core::slice::Iter<'_, (K, V)> {
+        self.entries.iter()
+    }
+
+    /// Iterates keys in the table's order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Iterates values in the table's order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Removes `k` in O(1) by moving the last entry into its position, which changes the order.
+    pub fn swap_remove(&mut self, k: &K) -> Option<V> {
+        let index = self.find(self.hasher.hash(k), k)?;
+        self.index
+            .swap_remove(index, key_order(&self.entries, &self.hasher));
+        let (_, v) = self.entries.swap_remove(index);
+        Some(v)
+    }
+
+    /// Removes `k` while preserving the order of the remaining entries. Every later entry shifts
+    /// down a position so this is O(n).
+    pub fn shift_remove(&mut self, k: &K) -> Option<V> {
+        let index = self.find(self.hasher.hash(k), k)?;
+        self.index
+            .shift_remove(index, key_order(&self.entries, &self.hasher));
+        let (_, v) = self.entries.remove(index);
+        Some(v)
+    }
+
+    /// Moves the entry at `from` to `to`, shifting the entries in between. Panics if either is out of bounds.
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        assert!(from < self.entries.len() && to < self.entries.len());
+        if from < to {
+            self.entries[from..=to].rotate_left(1);
+        } else {
+            self.entries[to..=from].rotate_right(1);
+        }
+        self.index.move_index(from, to);
+    }
+
+    /// Swaps the positions of two entries. Panics if either is out of bounds.
+    pub fn swap_indices(&mut self, a: usize, b: usize) {
+        assert!(a < self.entries.len() && b < self.entries.len());
+        if a == b {
+            return;
+        }
+        self.index
+            .swap(a, b, key_order(&self.entries, &self.hasher));
+        self.entries.swap(a, b);
+    }
+
+    fn find(&self, hash: u64, k: &K) -> Option<usize> {
+        let (entries, hasher) = (&self.entries, &self.hasher);
+        let (found, _) = self.index.find(
+            hash,
+            |index| hasher.key_eq(&entries[index].0, k),
+            |index| hasher.key_cmp(&entries[index].0, k),
+        );
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{OrderedHashTable, SimpleHasher};
+
+    fn keys(hash_table: &OrderedHashTable<&'static str, i32>) -> Vec<&'static str> {
+        hash_table.keys().copied().collect()
+    }
+
+    fn ordered_table() -> OrderedHashTable<&'static str, i32> {
+        let mut hash_table = OrderedHashTable::new();
+        for (i, name) in [
+            "gedalia", "theo", "aviva", "chani", "nachmi", "avery", "caine",
+        ]
+        .iter()
+        .enumerate()
+        {
+            hash_table.insert(*name, i as i32);
+        }
+        hash_table
+    }
+
+    #[test]
+    fn test_insertion_order_survives_resizing() {
+        let mut hash_table = ordered_table();
+        assert_eq!(
+            keys(&hash_table),
+            vec!["gedalia", "theo", "aviva", "chani", "nachmi", "avery", "caine"]
+        );
+
+        // replacing a value keeps its position
+        assert_eq!(hash_table.insert("theo", 100), Some(1));
+        assert_eq!(hash_table.get_index(1), Some((&"theo", &100)));
+        assert_eq!(hash_table.get_index_of(&"caine"), Some(6));
+
+        *hash_table.get_mut(&"gedalia").unwrap() = -1;
+        for v in hash_table.values_mut() {
+            *v += 1;
+        }
+        assert_eq!(
+            hash_table.values().copied().collect::<Vec<_>>(),
+            vec![0, 101, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(hash_table.len(), 7);
+        assert!(!hash_table.is_empty());
+    }
+
+    #[test]
+    fn test_swap_remove_and_shift_remove() {
+        let mut hash_table = ordered_table();
+
+        assert_eq!(hash_table.swap_remove(&"theo"), Some(1));
+        assert_eq!(
+            keys(&hash_table),
+            vec!["gedalia", "caine", "aviva", "chani", "nachmi", "avery"]
+        );
+
+        assert_eq!(hash_table.shift_remove(&"aviva"), Some(2));
+        assert_eq!(
+            keys(&hash_table),
+            vec!["gedalia", "caine", "chani", "nachmi", "avery"]
+        );
+        assert_eq!(hash_table.swap_remove(&"aviva"), None);
+        assert_eq!(hash_table.shift_remove(&"theo"), None);
+
+        for (i, (k, v)) in hash_table.iter().enumerate() {
+            assert_eq!(hash_table.get_index_of(k), Some(i));
+            assert_eq!(hash_table.get(k), Some(v));
+        }
+    }
+
+    #[test]
+    fn test_move_index_and_swap_indices() {
+        let mut hash_table = ordered_table();
+
+        hash_table.move_index(0, 3);
+        assert_eq!(
+            keys(&hash_table),
+            vec!["theo", "aviva", "chani", "gedalia", "nachmi", "avery", "caine"]
+        );
+
+        hash_table.move_index(6, 1);
+        assert_eq!(
+            keys(&hash_table),
+            vec!["theo", "caine", "aviva", "chani", "gedalia", "nachmi", "avery"]
+        );
+
+        hash_table.swap_indices(0, 6);
+        assert_eq!(
+            keys(&hash_table),
+            vec!["avery", "caine", "aviva", "chani", "gedalia", "nachmi", "theo"]
+        );
+
+        for (i, (k, _)) in hash_table.iter().enumerate() {
+            assert_eq!(hash_table.get_index_of(k), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_positions_stay_right_in_long_chains() {
+        // three hashes put every key in one of three long chains
+        struct FewHashes;
+        impl SimpleHasher<u64> for FewHashes {
+            fn hash(&self, k: &u64) -> u64 {
+                k % 3
+            }
+        }
+        let mut hash_table = OrderedHashTable::with_hasher(FewHashes);
+        let mut expected: Vec<u64> = (0..40).collect();
+        for &k in &expected {
+            hash_table.insert(k, k * 10);
+        }
+
+        hash_table.swap_indices(3, 4);
+        expected.swap(3, 4);
+        hash_table.swap_indices(30, 6);
+        expected.swap(30, 6);
+        hash_table.move_index(2, 25);
+        let k = expected.remove(2);
+        expected.insert(25, k);
+        hash_table.move_index(39, 0);
+        let k = expected.remove(39);
+        expected.insert(0, k);
+        assert_eq!(hash_table.swap_remove(&11), Some(110));
+        let position = expected.iter().position(|&k| k == 11).unwrap();
+        expected.swap_remove(position);
+        assert_eq!(hash_table.shift_remove(&20), Some(200));
+        expected.retain(|&k| k != 20);
+
+        assert_eq!(hash_table.keys().copied().collect::<Vec<_>>(), expected);
+        for (i, k) in expected.iter().enumerate() {
+            assert_eq!(hash_table.get_index_of(k), Some(i));
+            assert_eq!(hash_table.get(k), Some(&(k * 10)));
+        }
+        assert_eq!(hash_table.get(&11), None);
+        assert_eq!(hash_table.get(&20), None);
+    }
+}
@@ -0,0 +1,171 @@
+use alloc::collections::BTreeSet;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::hash::Hash;
+use core::ops::Bound;
+
+use crate::{DefaultSimpleHasher, Entry, HashTable, SimpleHasher};
+
+// orders keys by their string form so the index can be searched with a plain `&str`
+struct ByStr<K>(K);
+
+impl<K: AsRef<str>> PartialEq for ByStr<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_ref() == other.0.as_ref()
+    }
+}
+
+impl<K: AsRef<str>> Eq for ByStr<K> {}
+
+impl<K: AsRef<str>> PartialOrd for ByStr<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: AsRef<str>> Ord for ByStr<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.as_ref().cmp(other.0.as_ref())
+    }
+}
+
+impl<K: AsRef<str>> Borrow<str> for ByStr<K> {
+    fn borrow(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+/// A hash table with string keys that also keeps its keys in sorted order, so every key starting with
+/// a prefix can be found without scanning the whole table. Exact lookups still go through the hash
+/// table alone; inserts and removes pay an extra O(log n) to keep the index in step.
+///
+/// The index holds its own clone of every key, so keys are stored twice and must be `Clone`. For
+/// owned strings that doubles the memory keys take; keys that are cheap to clone and share their
+/// string, such as `&str` or `Arc<str>`, avoid the extra copy.
+///
+/// The hasher must only treat keys as equal when their strings are equal.
+pub struct PrefixHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, V, H>,
+    index: BTreeSet<ByStr<K>>,
+}
+
+impl<K, V> Default for PrefixHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        PrefixHashTable {
+            inner: Default::default(),
+            index: BTreeSet::new(),
+        }
+    }
+}
+
+impl<K, V> PrefixHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq + AsRef<str> + Clone,
+{
+    pub fn new() -> PrefixHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> PrefixHashTable<K, V, H>
+where
+    K: Hash + PartialEq + AsRef<str> + Clone,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> PrefixHashTable<K, V, H> {
+        PrefixHashTable {
+            inner: HashTable::with_hasher(hasher),
+            index: BTreeSet::new(),
+        }
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        // only a new key is cloned, into the index
+        match self.inner.entry(k) {
+            Entry::Occupied(mut e) => Some(e.insert(v)),
+            Entry::Vacant(e) => {
+                self.index.insert(ByStr(e.key().clone()));
+                e.insert(v);
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k)
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.inner.get_mut(k)
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let v = self.inner.remove(k)?;
+        self.index.remove(k.as_ref());
+        Some(v)
+    }
+
+    /// Iterates the entries whose keys start with `prefix`, in key order.
+    pub fn iter_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a K, &'a V)> {
+        self.index
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |k| k.0.as_ref().starts_with(prefix))
+            .map(move |k| {
+                let v = self
+                    .inner
+                    .get(&k.0)
+                    .expect("every indexed key is in the table");
+                (&k.0, v)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PrefixHashTable;
+
+    #[test]
+    fn test_iter_prefix() {
+        let mut hash_table = PrefixHashTable::new();
+        for (k, v) in [
+            ("user:123:name", "gedalia"),
+            ("user:123:age", "27"),
+            ("user:1234:name", "theo"),
+            ("user:12", "aviva"),
+            ("group:123:name", "kotts"),
+        ]
+        .iter()
+        {
+            hash_table.insert(String::from(*k), *v);
+        }
+        assert_eq!(
+            hash_table.insert(String::from("user:123:age"), "28"),
+            Some("27")
+        );
+
+        let found: Vec<_> = hash_table
+            .iter_prefix("user:123:")
+            .map(|(k, v)| (k.as_str(), *v))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("user:123:age", "28"), ("user:123:name", "gedalia")]
+        );
+        assert_eq!(hash_table.iter_prefix("user:").count(), 4);
+        assert_eq!(hash_table.iter_prefix("nowhere").count(), 0);
+
+        assert_eq!(
+            hash_table.remove(&String::from("user:123:name")),
+            Some("gedalia")
+        );
+        assert_eq!(hash_table.iter_prefix("user:123:").count(), 1);
+        assert_eq!(hash_table.get(&String::from("user:12")), Some(&"aviva"));
+    }
+}
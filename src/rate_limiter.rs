@@ -0,0 +1,177 @@
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::{DefaultSimpleHasher, HashTable, SimpleHasher};
+
+/// The outcome of `RateLimiterTable::check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allowed,
+    /// Denied until enough tokens have refilled, which takes `retry_after`.
+    Denied {
+        retry_after: Duration,
+    },
+}
+
+struct TokenBucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    // refills up to `now`, then spends a token if there is one
+    fn check(&mut self, now: Instant, rate: f64, burst: f64) -> Decision {
+        let elapsed = now.saturating_duration_since(self.refilled);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * rate).min(burst);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Decision::Allowed
+        } else {
+            Decision::Denied {
+                // tiny rates can put the next token further off than a `Duration` reaches
+                retry_after: Duration::try_from_secs_f64((1.0 - self.tokens) / rate)
+                    .unwrap_or(Duration::MAX),
+            }
+        }
+    }
+}
+
+/// Token bucket rate limiting per key, for limiting requests per user or per IP.
+///
+/// Each key gets a bucket of `burst` tokens refilling at `rate` tokens a second, and every allowed
+/// check spends a token. Buckets are only refilled when their key is checked. A bucket that has
+/// refilled completely holds no more state than a new one, so `remove_idle` can drop it.
+pub struct RateLimiterTable<K, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, TokenBucket, H>,
+    rate: f64,
+    burst: f64,
+}
+
+impl<K> RateLimiterTable<K, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new(rate: f64, burst: u32) -> RateLimiterTable<K, DefaultSimpleHasher> {
+        RateLimiterTable::with_hasher(rate, burst, DefaultSimpleHasher::new())
+    }
+}
+
+impl<K, H> RateLimiterTable<K, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    /// Panics unless `rate` is positive and finite and `burst` is at least one.
+    pub fn with_hasher(rate: f64, burst: u32, hasher: H) -> RateLimiterTable<K, H> {
+        assert!(
+            rate > 0.0 && rate.is_finite(),
+            "a rate must be positive and finite"
+        );
+        assert!(burst > 0, "a burst must allow at least one token");
+
+        RateLimiterTable {
+            inner: HashTable::with_hasher(hasher),
+            rate,
+            burst: burst as f64,
+        }
+    }
+
+    /// Spends one of `k`'s tokens if it has one.
+    pub fn check(&mut self, k: &K) -> Decision
+    where
+        K: Clone,
+    {
+        self.check_at(k, Instant::now())
+    }
+
+    /// Removes every key whose bucket has refilled completely, returning how many were removed.
+    pub fn remove_idle(&mut self) -> usize {
+        self.remove_idle_at(Instant::now())
+    }
+
+    fn check_at(&mut self, k: &K, now: Instant) -> Decision
+    where
+        K: Clone,
+    {
+        let (rate, burst) = (self.rate, self.burst);
+        // only a key seen for the first time is cloned
+        match self.inner.get_mut(k) {
+            Some(bucket) => bucket.check(now, rate, burst),
+            None => {
+                let mut bucket = TokenBucket {
+                    tokens: burst,
+                    refilled: now,
+                };
+                let decision = bucket.check(now, rate, burst);
+                self.inner.insert(k.clone(), bucket);
+                decision
+            }
+        }
+    }
+
+    fn remove_idle_at(&mut self, now: Instant) -> usize {
+        let (rate, burst) = (self.rate, self.burst);
+        self.inner.retain_entries(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.refilled);
+            bucket.tokens + elapsed.as_secs_f64() * rate < burst
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{Decision, RateLimiterTable};
+
+    #[test]
+    fn test_token_buckets() {
+        // two tokens a second with bursts of up to three
+        let mut limiter = RateLimiterTable::new(2.0, 3);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(&"gedalia", start), Decision::Allowed);
+        }
+        assert_eq!(
+            limiter.check_at(&"gedalia", start),
+            Decision::Denied {
+                retry_after: Duration::from_millis(500)
+            }
+        );
+        // keys are limited independently
+        assert_eq!(limiter.check_at(&"theo", start), Decision::Allowed);
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.check_at(&"gedalia", later), Decision::Allowed);
+        assert!(matches!(
+            limiter.check_at(&"gedalia", later),
+            Decision::Denied { .. }
+        ));
+
+        // theo refills completely after half a second, gedalia needs a second and a half
+        assert_eq!(limiter.remove_idle_at(later), 1);
+        assert_eq!(
+            limiter.remove_idle_at(later + Duration::from_millis(1500)),
+            1
+        );
+    }
+
+    #[test]
+    fn test_tiny_rates_dont_overflow_retry_after() {
+        let mut limiter = RateLimiterTable::new(1e-30, 1);
+        let start = Instant::now();
+        assert_eq!(limiter.check_at(&"gedalia", start), Decision::Allowed);
+        assert_eq!(
+            limiter.check_at(&"gedalia", start),
+            Decision::Denied {
+                retry_after: Duration::MAX
+            }
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::{HashTable, SimpleHasher, MAX_PREALLOCATED_ENTRIES};
+
+/// Serializes as a map of the table's entries, in no particular order.
+impl<K, V, H> Serialize for HashTable<K, V, H>
+where
+    K: Hash + Serialize,
+    V: Serialize,
+    H: SimpleHasher<K>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (k, v) in self {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+/// Deserializes from a map, inserting each entry into a new table so every key lands in the bucket
+/// its hash picks here. A repeated key keeps its last value.
+impl<'de, K, V, H> Deserialize<'de> for HashTable<K, V, H>
+where
+    K: Hash + PartialEq + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: SimpleHasher<K> + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(HashTableVisitor {
+            hasher: H::default(),
+            marker: PhantomData,
+        })
+    }
+}
+
+struct HashTableVisitor<K, V, H> {
+    hasher: H,
+    marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'de, K, V, H> Visitor<'de> for HashTableVisitor<K, V, H>
+where
+    K: Hash + PartialEq + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: SimpleHasher<K>,
+{
+    type Value = HashTable<K, V, H>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut hash_table = HashTable::with_hasher(self.hasher);
+        hash_table.reserve_exact(
+            access
+                .size_hint()
+                .unwrap_or(0)
+                .min(MAX_PREALLOCATED_ENTRIES),
+        );
+        while let Some((k, v)) = access.next_entry()? {
+            hash_table.insert(k, v);
+        }
+        Ok(hash_table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FnvSimpleHasher, HashTable};
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut hash_table = HashTable::new();
+        for k in 0..50 {
+            hash_table.insert(k.to_string(), k);
+        }
+
+        let json = serde_json::to_string(&hash_table).unwrap();
+        let decoded: HashTable<String, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, hash_table);
+        assert_eq!(decoded["42"], 42);
+
+        let decoded: HashTable<String, i32, FnvSimpleHasher> =
+            serde_json::from_str(r#"{"gedalia": 27, "theo": 28, "gedalia": 29}"#).unwrap();
+        assert_eq!(decoded["gedalia"], 29);
+        assert_eq!(decoded["theo"], 28);
+        assert_eq!(
+            serde_json::to_string(&HashTable::<i32, i32>::new()).unwrap(),
+            "{}"
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use core::borrow::Borrow;
+use core::hash::Hash;
+use core::iter::Chain;
+
+use crate::{DefaultSimpleHasher, HashTable, HashTableIntoIter, HashTableIterator, SimpleHasher};
+
+/// A set of values backed by a `HashTable` with unit values.
+pub struct HashTableSet<T, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<T>,
+    T: Hash,
+{
+    inner: HashTable<T, (), H>,
+}
+
+impl<T> Default for HashTableSet<T, DefaultSimpleHasher>
+where
+    T: Hash,
+{
+    fn default() -> Self {
+        HashTableSet {
+            inner: Default::default(),
+        }
+    }
+}
+
+impl<T> HashTableSet<T, DefaultSimpleHasher>
+where
+    T: Hash + PartialEq,
+{
+    pub fn new() -> HashTableSet<T, DefaultSimpleHasher> {
+        Default::default()
+    }
+
+    /// A set that holds `capacity` values without resizing.
+    pub fn with_capacity(capacity: usize) -> HashTableSet<T, DefaultSimpleHasher> {
+        HashTableSet {
+            inner: HashTable::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T, H> HashTableSet<T, H>
+where
+    T: Hash + PartialEq,
+    H: SimpleHasher<T>,
+{
+    pub fn with_hasher(hasher: H) -> HashTableSet<T, H> {
+        HashTableSet {
+            inner: HashTable::with_hasher(hasher),
+        }
+    }
+
+    /// Adds `t` to the set, returning whether it was newly added.
+    pub fn insert(&mut self, t: T) -> bool {
+        self.inner.insert(t, ()).is_none()
+    }
+
+    pub fn contains<Q>(&self, t: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        self.inner.get(t).is_some()
+    }
+
+    /// Takes `t` out of the set, returning whether it was there.
+    pub fn remove<Q>(&mut self, t: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+        H: SimpleHasher<Q>,
+    {
+        self.inner.remove(t).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn iter(&self) -> SetIter<'_, T> {
+        SetIter {
+            inner: (&self.inner).into_iter(),
+        }
+    }
+
+    pub fn is_disjoint(&self, other: &HashTableSet<T, H>) -> bool {
+        self.inner.is_disjoint(&other.inner)
+    }
+
+    pub fn is_subset(&self, other: &HashTableSet<T, H>) -> bool {
+        self.inner.len() <= other.inner.len() && self.iter().all(|t| other.contains(t))
+    }
+
+    pub fn is_superset(&self, other: &HashTableSet<T, H>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Lazily yields every value in either set, each exactly once.
+    pub fn union<'a>(&'a self, other: &'a HashTableSet<T, H>) -> Union<'a, T, H> {
+        Union {
+            iter: self.iter().chain(other.difference(self)),
+        }
+    }
+
+    /// Lazily yields the values present in both sets, iterating the smaller one.
+    pub fn intersection<'a>(&'a self, other: &'a HashTableSet<T, H>) -> Intersection<'a, T, H> {
+        let (smaller, larger) = if self.inner.len() <= other.inner.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Intersection {
+            iter: smaller.iter(),
+            other: larger,
+        }
+    }
+
+    /// Lazily yields the values in `self` that are not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a HashTableSet<T, H>) -> Difference<'a, T, H> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Lazily yields the values in exactly one of the two sets.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a HashTableSet<T, H>,
+    ) -> SymmetricDifference<'a, T, H> {
+        SymmetricDifference {
+            iter: self.difference(other).chain(other.difference(self)),
+        }
+    }
+}
+
+impl<'a, T, H> IntoIterator for &'a HashTableSet<T, H>
+where
+    T: Hash + PartialEq,
+    H: SimpleHasher<T>,
+{
+    type Item = &'a T;
+
+    type IntoIter = SetIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, H> IntoIterator for HashTableSet<T, H>
+where
+    T: Hash,
+    H: SimpleHasher<T>,
+{
+    type Item = T;
+
+    type IntoIter = SetIntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SetIntoIter {
+            inner: self.inner.into_iter(),
+        }
+    }
+}
+
+impl<T> core::iter::FromIterator<T> for HashTableSet<T, DefaultSimpleHasher>
+where
+    T: Hash + PartialEq,
+{
+    fn from_iter<I>(values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut set = HashTableSet::new();
+        set.extend(values);
+        set
+    }
+}
+
+impl<T, H> Extend<T> for HashTableSet<T, H>
+where
+    T: Hash + PartialEq,
+    H: SimpleHasher<T>,
+{
+    fn extend<I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.inner.extend(values.into_iter().map(|t| (t, ())));
+    }
+}
+
+pub struct SetIter<'a, T> {
+    inner: HashTableIterator<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for SetIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(t, _)| t)
+    }
+}
+
+pub struct SetIntoIter<T> {
+    inner: HashTableIntoIter<T, ()>,
+}
+
+impl<T> Iterator for SetIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(t, _)| t)
+    }
+}
+
+pub struct Union<'a, T, H>
+where
+    T: Hash,
+    H: SimpleHasher<T>,
+{
+    iter: Chain<SetIter<'a, T>, Difference<'a, T, H>>,
+}
+
+impl<'a, T, H> Iterator for Union<'a, T, H>
+where
+    T: Hash + PartialEq,
+    H: SimpleHasher<T>,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+pub struct Intersection<'a, T, H>
+where
+    T: Hash,
+    H: SimpleHasher<T>,
+{
+    iter: SetIter<'a, T>,
+    other: &'a HashTableSet<T, H>,
+}
+
+impl<'a, T, H> Iterator for Intersection<'a, T, H>
+where
+    T: Hash + PartialEq,
+    H: SimpleHasher<T>,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|t| other.contains(t))
+    }
+}
+
+pub struct Difference<'a, T, H>
+where
+    T: Hash,
+    H: SimpleHasher<T>,
+{
+    iter: SetIter<'a, T>,
+    other: &'a HashTableSet<T, H>,
+}
+
+impl<'a, T, H> Iterator for Difference<'a, T, H>
+where
+    T: Hash + PartialEq,
+    H: SimpleHasher<T>,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|t| !other.contains(t))
+    }
+}
+
+pub struct SymmetricDifference<'a, T, H>
+where
+    T: Hash,
+    H: SimpleHasher<T>,
+{
+    iter: Chain<Difference<'a, T, H>, Difference<'a, T, H>>,
+}
+
+impl<'a, T, H> Iterator for SymmetricDifference<'a, T, H>
+where
+    T: Hash + PartialEq,
+    H: SimpleHasher<T>,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HashTableSet;
+
+    fn set_of(values: &[i32]) -> HashTableSet<i32> {
+        let mut set = HashTableSet::new();
+        for v in values {
+            set.insert(*v);
+        }
+        set
+    }
+
+    fn sorted<'a>(values: impl Iterator<Item = &'a i32>) -> Vec<i32> {
+        let mut values: Vec<i32> = values.copied().collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn test_insert_remove_and_collect() {
+        let mut set: HashTableSet<String> = ["gedalia", "theo", "gedalia"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains("theo"));
+
+        assert!(!set.insert(String::from("theo")));
+        assert!(set.remove("theo"));
+        assert!(!set.remove("theo"));
+        assert!(!set.contains("theo"));
+
+        set.extend(vec![String::from("aviva"), String::from("chani")]);
+        let mut names: Vec<String> = set.into_iter().collect();
+        names.sort();
+        assert_eq!(names, vec!["aviva", "chani", "gedalia"]);
+
+        let empty: HashTableSet<i32> = HashTableSet::with_capacity(16);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_set_relations() {
+        let small = set_of(&[1, 2]);
+        let large = set_of(&[1, 2, 3]);
+        let other = set_of(&[4, 5]);
+
+        assert!(small.is_subset(&large));
+        assert!(!large.is_subset(&small));
+        assert!(large.is_superset(&small));
+        assert!(small.is_subset(&small));
+        assert!(small.is_disjoint(&other));
+        assert!(!small.is_disjoint(&large));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set_of(&[1, 2, 3, 4]);
+        let b = set_of(&[3, 4, 5]);
+
+        assert_eq!(sorted(a.union(&b)), vec![1, 2, 3, 4, 5]);
+        assert_eq!(sorted(a.intersection(&b)), vec![3, 4]);
+        assert_eq!(sorted(b.intersection(&a)), vec![3, 4]);
+        assert_eq!(sorted(a.difference(&b)), vec![1, 2]);
+        assert_eq!(sorted(b.difference(&a)), vec![5]);
+        assert_eq!(sorted(a.symmetric_difference(&b)), vec![1, 2, 5]);
+    }
+}
@@ -0,0 +1,790 @@
+use std::hash::Hash;
+
+use crate::sync::{
+    Arc, AtomicU64, Condvar, Mutex, MutexGuard, Ordering, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+use crate::{DefaultSimpleHasher, HashTable, SimpleHasher};
+
+pub(crate) const DEFAULT_NUMBER_OF_SHARDS: usize = 16;
+
+// how many buckets of a shard's previous table each write moves into its current one
+const MIGRATION_STEP: usize = 8;
+
+enum Computation<V> {
+    Pending,
+    Done(V),
+    // the computing caller failed or panicked, so a waiter has to take over
+    Abandoned,
+}
+
+// a value somebody is currently computing, which other callers for the same key wait on
+struct InFlight<V> {
+    computation: Mutex<Computation<V>>,
+    finished: Condvar,
+}
+
+struct Shard<K, V, H>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    table: RwLock<ShardTable<K, V, H>>,
+    in_flight: Mutex<HashTable<K, Arc<InFlight<V>>, H>>,
+    // bumped on every write so read caches can tell whether what they hold from this shard is stale
+    version: AtomicU64,
+}
+
+impl<K, V, H> Shard<K, V, H>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    fn write_table(&self) -> RwLockWriteGuard<'_, ShardTable<K, V, H>> {
+        let table = write(&self.table);
+        // bumping while the lock is held means a reader that sees the old version can't have read
+        // anything this write changes
+        self.version.fetch_add(1, Ordering::Release);
+        table
+    }
+}
+
+// a shard's entries. instead of rehashing everything at once when it fills up, a shard starts a new
+// table twice the size and every write moves a few buckets of the old one across, so no single write
+// pays for the whole rehash. until the old table is empty a key lives in exactly one of the two
+struct ShardTable<K, V, H>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    current: HashTable<K, V, H>,
+    previous: Option<Migration<K, V, H>>,
+}
+
+struct Migration<K, V, H>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    table: HashTable<K, V, H>,
+    // buckets before this one have already been moved
+    next_bucket: usize,
+}
+
+impl<K, V, H> ShardTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K> + Clone,
+{
+    fn get(&self, k: &K) -> Option<&V> {
+        self.current.get(k).or_else(|| {
+            self.previous
+                .as_ref()
+                .and_then(|migration| migration.table.get(k))
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.current.len()
+            + self
+                .previous
+                .as_ref()
+                .map_or(0, |migration| migration.table.len())
+    }
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.grow_if_full();
+        self.migrate();
+
+        let old_v = self
+            .previous
+            .as_mut()
+            .and_then(|migration| migration.table.remove(&k));
+        self.current.insert(k, v).or(old_v)
+    }
+
+    // inserts `v` only if `k` has no value yet, returning whichever value ends up stored
+    fn get_or_insert(&mut self, k: K, v: V) -> &V {
+        self.grow_if_full();
+        self.migrate();
+
+        // a value still waiting to be migrated comes across rather than being replaced
+        let old_v = self
+            .previous
+            .as_mut()
+            .and_then(|migration| migration.table.remove(&k));
+        self.current.entry(k).or_insert(old_v.unwrap_or(v))
+    }
+
+    fn grow_if_full(&mut self) {
+        if self.previous.is_none() && self.current.would_grow() {
+            let bigger =
+                HashTable::from_parts(self.current.capacity() * 2, self.current.hasher.clone());
+            let table = std::mem::replace(&mut self.current, bigger);
+            self.previous = Some(Migration {
+                table,
+                next_bucket: 0,
+            });
+        }
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.migrate();
+        self.current.remove(k).or_else(|| {
+            self.previous
+                .as_mut()
+                .and_then(|migration| migration.table.remove(k))
+        })
+    }
+
+    fn migrate(&mut self) {
+        let migration = match &mut self.previous {
+            Some(migration) => migration,
+            None => return,
+        };
+        let end = (migration.next_bucket + MIGRATION_STEP).min(migration.table.capacity());
+        for bucket_index in migration.next_bucket..end {
+            for (k, v) in migration.table.take_bucket(bucket_index) {
+                let hash = self.current.hasher.hash(&k);
+                // a key is never in both tables, so this can skip the duplicate check
+                self.current._insert(k, v, hash);
+            }
+        }
+        migration.next_bucket = end;
+        if end == migration.table.capacity() {
+            self.previous = None;
+        }
+    }
+}
+
+/// A hash table that can be shared between threads. Keys are split across independently locked
+/// shards so writers to different shards don't contend.
+pub struct ShardedHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    shards: Vec<Shard<K, V, H>>,
+    hasher: H,
+}
+
+impl<K, V> Default for ShardedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        ShardedHashTable::with_shards(DEFAULT_NUMBER_OF_SHARDS)
+    }
+}
+
+impl<K, V> ShardedHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> ShardedHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+
+    pub fn with_shards(number_of_shards: usize) -> ShardedHashTable<K, V, DefaultSimpleHasher> {
+        ShardedHashTable::with_shards_and_hasher(number_of_shards, DefaultSimpleHasher::new())
+    }
+}
+
+impl<K, V, H> ShardedHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K> + Clone,
+{
+    /// Panics if `number_of_shards` is zero.
+    pub fn with_shards_and_hasher(number_of_shards: usize, hasher: H) -> ShardedHashTable<K, V, H> {
+        assert!(
+            number_of_shards > 0,
+            "a sharded table needs at least one shard"
+        );
+        let mut shards = vec![];
+        for _ in 0..number_of_shards {
+            shards.push(Shard {
+                table: RwLock::new(ShardTable {
+                    current: HashTable::with_hasher(hasher.clone()),
+                    previous: None,
+                }),
+                version: AtomicU64::new(0),
+                in_flight: Mutex::new(HashTable::with_hasher(hasher.clone())),
+            });
+        }
+
+        ShardedHashTable { shards, hasher }
+    }
+}
+
+impl<K, V, H> ShardedHashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K> + Clone,
+{
+    pub fn insert(&self, k: K, v: V) -> Option<V> {
+        self.shard(&k).write_table().insert(k, v)
+    }
+
+    /// Returns a copy of the value so no lock is held once this returns.
+    pub fn get(&self, k: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        read(&self.shard(k).table).get(k).cloned()
+    }
+
+    pub fn remove(&self, k: &K) -> Option<V> {
+        self.shard(k).write_table().remove(k)
+    }
+
+    /// The number of entries across all shards. Shards are counted one after another, so writes racing
+    /// with this can make the total one no single moment had.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| read(&shard.table).len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards
+            .iter()
+            .all(|shard| read(&shard.table).len() == 0)
+    }
+
+    /// Iterates copies of the table's entries. Each shard is copied in one go under its read lock when
+    /// iteration reaches it, so every shard is seen as it was at a single moment and no lock is held
+    /// between entries, but writes to other shards meanwhile may or may not show up.
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.shards.iter().flat_map(|shard| {
+            let table = read(&shard.table);
+            let mut entries: Vec<(K, V)> = (&table.current)
+                .into_iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            if let Some(migration) = &table.previous {
+                entries.extend(
+                    (&migration.table)
+                        .into_iter()
+                        .map(|(k, v)| (k.clone(), v.clone())),
+                );
+            }
+            entries
+        })
+    }
+
+    /// Returns the value for `k`, computing and inserting it with `f` if it is missing.
+    ///
+    /// Concurrent callers missing the same key don't all run their computation: the first one does while
+    /// the rest wait for its result. Errors are not cached. If the computing caller's `f` returns an error
+    /// that caller gets it back, and if it panics the panic unwinds through that caller; in both cases one
+    /// of the waiting callers takes over and runs its own `f`.
+    pub fn get_or_compute<F, E>(&self, k: K, f: F) -> Result<V, E>
+    where
+        K: Clone,
+        V: Clone,
+        F: FnOnce(&K) -> Result<V, E>,
+    {
+        let shard = self.shard(&k);
+        loop {
+            if let Some(v) = read(&shard.table).get(&k) {
+                return Ok(v.clone());
+            }
+
+            let mut in_flight = lock(&shard.in_flight);
+            // check again now that nothing can start or finish computing this key: a computing caller
+            // inserts its value before it stops being in flight
+            if let Some(v) = read(&shard.table).get(&k) {
+                return Ok(v.clone());
+            }
+
+            if let Some(computing) = in_flight.get(&k) {
+                let computing = Arc::clone(computing);
+                drop(in_flight);
+
+                let mut computation = lock(&computing.computation);
+                while let Computation::Pending = *computation {
+                    computation = computing
+                        .finished
+                        .wait(computation)
+                        .unwrap_or_else(|e| e.into_inner());
+                }
+                match &*computation {
+                    Computation::Done(v) => return Ok(v.clone()),
+                    // go around again, most likely becoming the one to compute it
+                    _ => continue,
+                }
+            }
+
+            let computing = Arc::new(InFlight {
+                computation: Mutex::new(Computation::Pending),
+                finished: Condvar::new(),
+            });
+            in_flight.insert(k.clone(), Arc::clone(&computing));
+            drop(in_flight);
+
+            let mut guard = ComputingGuard {
+                shard,
+                k: &k,
+                computing,
+                outcome: None,
+            };
+            let v = f(&k)?;
+            // a plain insert may have stored a value while this one was being computed, which wins
+            let v = shard.write_table().get_or_insert(k.clone(), v).clone();
+            guard.outcome = Some(v.clone());
+            return Ok(v);
+        }
+    }
+
+    /// A cache of recently read entries for use by a single thread, in front of this table. Hot keys
+    /// read through it skip the shard's lock entirely while their shard isn't being written to.
+    ///
+    /// The cache holds up to `capacity` entries, each in a slot picked by its key's hash. Entries are
+    /// invalidated by any write to their shard, not just to their key. Panics if `capacity` is zero.
+    pub fn read_cache(&self, capacity: usize) -> ReadCache<'_, K, V, H> {
+        assert!(capacity > 0, "a read cache needs at least one slot");
+        ReadCache {
+            table: self,
+            slots: (0..capacity).map(|_| None).collect(),
+        }
+    }
+
+    fn shard(&self, k: &K) -> &Shard<K, V, H> {
+        &self.shards[self.shard_index(self.hasher.hash(k))]
+    }
+
+    fn shard_index(&self, hash: u64) -> usize {
+        // the high bits pick the shard so they stay independent of the low bits each shard's own
+        // table uses to pick buckets
+        (hash >> 32) as usize % self.shards.len()
+    }
+}
+
+struct Cached<K, V> {
+    k: K,
+    v: V,
+    shard_index: usize,
+    // the shard's version from before the value was read
+    version: u64,
+}
+
+/// A small per-thread cache of entries read from a `ShardedHashTable`, created by
+/// `ShardedHashTable::read_cache`.
+pub struct ReadCache<'a, K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    table: &'a ShardedHashTable<K, V, H>,
+    slots: Vec<Option<Cached<K, V>>>,
+}
+
+impl<'a, K, V, H> ReadCache<'a, K, V, H>
+where
+    K: Hash + PartialEq + Clone,
+    V: Clone,
+    H: SimpleHasher<K> + Clone,
+{
+    /// Like `ShardedHashTable::get`, answering from the cache while the entry's shard is unchanged.
+    pub fn get(&mut self, k: &K) -> Option<V> {
+        let hash = self.table.hasher.hash(k);
+        let shard_index = self.table.shard_index(hash);
+        let shard = &self.table.shards[shard_index];
+        let version = shard.version.load(Ordering::Acquire);
+        let slot = hash as usize % self.slots.len();
+
+        if let Some(cached) = &self.slots[slot] {
+            if cached.shard_index == shard_index
+                && cached.version == version
+                && self.table.hasher.key_eq(&cached.k, k)
+            {
+                return Some(cached.v.clone());
+            }
+        }
+
+        let v = read(&shard.table).get(k).cloned();
+        // only hits are cached, and they are tagged with the version read before them so a write
+        // racing with the read leaves the entry already stale
+        self.slots[slot] = v.as_ref().map(|v| Cached {
+            k: k.clone(),
+            v: v.clone(),
+            shard_index,
+            version,
+        });
+        v
+    }
+}
+
+// publishes the outcome of a computation to its waiters once the computing caller is done, however
+// it got there: returning a value, returning an error or panicking
+struct ComputingGuard<'a, K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K> + Clone,
+{
+    shard: &'a Shard<K, V, H>,
+    k: &'a K,
+    computing: Arc<InFlight<V>>,
+    outcome: Option<V>,
+}
+
+impl<'a, K, V, H> Drop for ComputingGuard<'a, K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K> + Clone,
+{
+    fn drop(&mut self) {
+        // this may run while unwinding, so poisoned locks are recovered rather than panicking again
+        let mut in_flight = self
+            .shard
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        in_flight.remove(self.k);
+
+        let mut computation = self
+            .computing
+            .computation
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *computation = match self.outcome.take() {
+            Some(v) => Computation::Done(v),
+            None => Computation::Abandoned,
+        };
+        self.computing.finished.notify_all();
+    }
+}
+
+pub(crate) fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read()
+        .expect("a thread panicked while writing to a shard")
+}
+
+pub(crate) fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write()
+        .expect("a thread panicked while writing to a shard")
+}
+
+fn lock<T>(lock: &Mutex<T>) -> MutexGuard<'_, T> {
+    lock.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::write;
+    use crate::ShardedHashTable;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let hash_table = ShardedHashTable::new();
+        assert_eq!(hash_table.insert("gedalia", 27), None);
+        assert_eq!(hash_table.insert("gedalia", 28), Some(27));
+        assert_eq!(hash_table.get(&"gedalia"), Some(28));
+        assert_eq!(hash_table.remove(&"gedalia"), Some(28));
+        assert_eq!(hash_table.get(&"gedalia"), None);
+    }
+
+    #[test]
+    fn test_len_and_iter_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ShardedHashTable<String, Vec<u8>>>();
+
+        let hash_table = ShardedHashTable::with_shards(4);
+        thread::scope(|s| {
+            for t in 0..4 {
+                let hash_table = &hash_table;
+                s.spawn(move || {
+                    for k in 0..100 {
+                        hash_table.insert(t * 100 + k, t);
+                    }
+                });
+            }
+        });
+        assert_eq!(hash_table.len(), 400);
+        assert!(!hash_table.is_empty());
+
+        let mut entries: Vec<(i32, i32)> = hash_table.iter().collect();
+        entries.sort();
+        assert_eq!(entries.len(), 400);
+        assert!(entries.iter().all(|&(k, t)| k / 100 == t));
+
+        // entries still waiting to move out of a shard's old table are counted and iterated too
+        let hash_table = ShardedHashTable::with_shards(1);
+        for k in 0..8 {
+            hash_table.insert(k, k);
+        }
+        assert!(hash_table.shards[0]
+            .table
+            .read()
+            .unwrap()
+            .previous
+            .is_some());
+        assert_eq!(hash_table.len(), 8);
+        assert_eq!(hash_table.iter().count(), 8);
+        for k in 0..8 {
+            hash_table.remove(&k);
+        }
+        assert!(hash_table.is_empty());
+    }
+
+    #[test]
+    fn test_shards_grow_incrementally() {
+        let hash_table = ShardedHashTable::with_shards(1);
+        // the shard starts with 10 buckets, so the eighth entry starts moving them to a bigger table
+        for k in 0..8 {
+            hash_table.insert(k, k);
+        }
+        {
+            let shard = hash_table.shards[0].table.read().unwrap();
+            let migration = shard.previous.as_ref().expect("a migration has started");
+            assert_eq!(shard.current.capacity(), 20);
+            assert!(migration.next_bucket < 10);
+        }
+        for k in 0..8 {
+            assert_eq!(hash_table.get(&k), Some(k));
+        }
+
+        assert_eq!(hash_table.insert(0, 100), Some(0));
+        assert_eq!(hash_table.remove(&7), Some(7));
+        assert!(hash_table.shards[0]
+            .table
+            .read()
+            .unwrap()
+            .previous
+            .is_none());
+        assert_eq!(hash_table.get(&0), Some(100));
+        assert_eq!(hash_table.get(&7), None);
+        for k in 1..7 {
+            assert_eq!(hash_table.get(&k), Some(k));
+        }
+    }
+
+    #[test]
+    fn test_read_cache_is_invalidated_by_writes() {
+        let hash_table = ShardedHashTable::new();
+        hash_table.insert("gedalia", 27);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                let mut cache = hash_table.read_cache(16);
+                assert_eq!(cache.get(&"gedalia"), Some(27));
+                assert_eq!(cache.get(&"theo"), None);
+            });
+        });
+
+        let mut cache = hash_table.read_cache(16);
+        assert_eq!(cache.get(&"gedalia"), Some(27));
+        // sneak a change past the shard's version, which a cache hit can't notice
+        write(&hash_table.shard(&"gedalia").table).insert("gedalia", 0);
+        assert_eq!(cache.get(&"gedalia"), Some(27));
+
+        hash_table.insert("gedalia", 28);
+        assert_eq!(cache.get(&"gedalia"), Some(28));
+        hash_table.remove(&"gedalia");
+        assert_eq!(cache.get(&"gedalia"), None);
+    }
+
+    #[test]
+    fn test_get_or_compute_runs_once_per_key() {
+        let hash_table = ShardedHashTable::new();
+        let computations = AtomicUsize::new(0);
+        let number_of_threads = 8;
+        let barrier = Barrier::new(number_of_threads);
+
+        thread::scope(|s| {
+            for _ in 0..number_of_threads {
+                s.spawn(|| {
+                    barrier.wait();
+                    let v = hash_table.get_or_compute("gedalia", |_| {
+                        computations.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(50));
+                        Ok::<_, ()>(27)
+                    });
+                    assert_eq!(v, Ok(27));
+                });
+            }
+        });
+
+        assert_eq!(computations.load(Ordering::SeqCst), 1);
+        assert_eq!(hash_table.get(&"gedalia"), Some(27));
+    }
+
+    #[test]
+    fn test_get_or_compute_keeps_a_value_inserted_while_computing() {
+        let hash_table = ShardedHashTable::new();
+        let barrier = Barrier::new(2);
+
+        thread::scope(|s| {
+            let computing = s.spawn(|| {
+                hash_table.get_or_compute("gedalia", |_| {
+                    barrier.wait();
+                    // hold off returning until the insert below has landed
+                    barrier.wait();
+                    Ok::<_, ()>(27)
+                })
+            });
+
+            barrier.wait();
+            assert_eq!(hash_table.insert("gedalia", 28), None);
+            barrier.wait();
+            assert_eq!(computing.join().unwrap(), Ok(28));
+        });
+
+        assert_eq!(hash_table.get(&"gedalia"), Some(28));
+    }
+
+    #[test]
+    fn test_get_or_compute_errors_are_not_cached() {
+        let hash_table: ShardedHashTable<&str, i32> = ShardedHashTable::new();
+
+        assert_eq!(
+            hash_table.get_or_compute("gedalia", |_| Err("offline")),
+            Err("offline")
+        );
+        assert_eq!(hash_table.get(&"gedalia"), None);
+        assert_eq!(
+            hash_table.get_or_compute("gedalia", |_| Ok::<_, &str>(27)),
+            Ok(27)
+        );
+    }
+
+    #[test]
+    fn test_get_or_compute_recovers_from_a_panicking_computation() {
+        let hash_table: ShardedHashTable<&str, i32> = ShardedHashTable::new();
+        let barrier = Barrier::new(2);
+
+        thread::scope(|s| {
+            let panicking = s.spawn(|| {
+                hash_table.get_or_compute::<_, ()>("gedalia", |_| {
+                    barrier.wait();
+                    thread::sleep(Duration::from_millis(50));
+                    panic!("computation failed")
+                })
+            });
+            let waiting = s.spawn(|| {
+                barrier.wait();
+                hash_table.get_or_compute("gedalia", |_| Ok::<_, ()>(27))
+            });
+
+            assert!(panicking.join().is_err());
+            assert_eq!(waiting.join().unwrap(), Ok(27));
+        });
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::sync::atomic::{AtomicUsize, Ordering};
+    use loom::sync::Arc;
+    use loom::thread;
+
+    use crate::ShardedHashTable;
+
+    #[test]
+    fn loom_inserts_during_a_migration_are_not_lost() {
+        loom::model(|| {
+            let hash_table = Arc::new(ShardedHashTable::with_shards(1));
+            // one more entry starts moving the shard to a bigger table
+            for k in 0..7 {
+                hash_table.insert(k, k);
+            }
+
+            let threads: Vec<_> = (7..9)
+                .map(|k| {
+                    let hash_table = Arc::clone(&hash_table);
+                    thread::spawn(move || {
+                        assert_eq!(hash_table.insert(k, k), None);
+                    })
+                })
+                .collect();
+            assert_eq!(hash_table.remove(&0), Some(0));
+            for t in threads {
+                t.join().unwrap();
+            }
+
+            assert_eq!(hash_table.get(&0), None);
+            for k in 1..9 {
+                assert_eq!(hash_table.get(&k), Some(k));
+            }
+        });
+    }
+
+    #[test]
+    fn loom_get_or_compute_runs_once_per_key() {
+        loom::model(|| {
+            let hash_table = Arc::new(ShardedHashTable::with_shards(1));
+            let computations = Arc::new(AtomicUsize::new(0));
+
+            let threads: Vec<_> = (0..2)
+                .map(|_| {
+                    let hash_table = Arc::clone(&hash_table);
+                    let computations = Arc::clone(&computations);
+                    thread::spawn(move || {
+                        hash_table.get_or_compute("gedalia", |_| {
+                            computations.fetch_add(1, Ordering::SeqCst);
+                            Ok::<_, ()>(27)
+                        })
+                    })
+                })
+                .collect();
+            for t in threads {
+                assert_eq!(t.join().unwrap(), Ok(27));
+            }
+
+            assert_eq!(computations.load(Ordering::SeqCst), 1);
+            assert_eq!(hash_table.get(&"gedalia"), Some(27));
+        });
+    }
+
+    #[test]
+    fn loom_get_or_compute_recovers_from_an_error() {
+        loom::model(|| {
+            let hash_table = Arc::new(ShardedHashTable::with_shards(1));
+
+            let failing = {
+                let hash_table = Arc::clone(&hash_table);
+                thread::spawn(move || hash_table.get_or_compute("gedalia", |_| Err("offline")))
+            };
+            let v = hash_table.get_or_compute("gedalia", |_| Ok::<_, &str>(27));
+
+            // whichever order they ran in, the error wasn't cached and the value was
+            assert_eq!(v, Ok(27));
+            assert!(matches!(failing.join().unwrap(), Ok(27) | Err("offline")));
+            assert_eq!(hash_table.get(&"gedalia"), Some(27));
+        });
+    }
+
+    #[test]
+    fn loom_read_cache_never_serves_an_overwritten_value() {
+        loom::model(|| {
+            let hash_table = Arc::new(ShardedHashTable::with_shards(1));
+            hash_table.insert("gedalia", 27);
+
+            let writer = {
+                let hash_table = Arc::clone(&hash_table);
+                thread::spawn(move || {
+                    hash_table.insert("gedalia", 28);
+                })
+            };
+            let mut cache = hash_table.read_cache(1);
+            let first = cache.get(&"gedalia");
+            assert!(matches!(first, Some(27) | Some(28)));
+            // once the new value has been seen the old one never comes back
+            let second = cache.get(&"gedalia");
+            assert!(second >= first);
+
+            writer.join().unwrap();
+            assert_eq!(cache.get(&"gedalia"), Some(28));
+        });
+    }
+}
@@ -0,0 +1,121 @@
+use alloc::collections::BTreeSet;
+use core::hash::Hash;
+use core::ops::RangeBounds;
+
+use crate::{DefaultSimpleHasher, Entry, HashTable, SimpleHasher};
+
+/// A hash table that also keeps its keys in a sorted index, so ranges of keys can be iterated in
+/// order. Meant for workloads that are mostly point lookups with the occasional range query: lookups
+/// only touch the hash table, while inserts and removes pay an extra O(log n) to keep the index in step.
+///
+/// The hasher must only treat keys as equal when `Ord` does.
+pub struct SortedIndexHashTable<K, V, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, V, H>,
+    index: BTreeSet<K>,
+}
+
+impl<K, V> Default for SortedIndexHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash,
+{
+    fn default() -> Self {
+        SortedIndexHashTable {
+            inner: Default::default(),
+            index: BTreeSet::new(),
+        }
+    }
+}
+
+impl<K, V> SortedIndexHashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + Ord + Clone,
+{
+    pub fn new() -> SortedIndexHashTable<K, V, DefaultSimpleHasher> {
+        Default::default()
+    }
+}
+
+impl<K, V, H> SortedIndexHashTable<K, V, H>
+where
+    K: Hash + Ord + Clone,
+    H: SimpleHasher<K>,
+{
+    pub fn with_hasher(hasher: H) -> SortedIndexHashTable<K, V, H> {
+        SortedIndexHashTable {
+            inner: HashTable::with_hasher(hasher),
+            index: BTreeSet::new(),
+        }
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        // only a new key is cloned, into the index
+        match self.inner.entry(k) {
+            Entry::Occupied(mut e) => Some(e.insert(v)),
+            Entry::Vacant(e) => {
+                self.index.insert(e.key().clone());
+                e.insert(v);
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k)
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.inner.get_mut(k)
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let v = self.inner.remove(k)?;
+        self.index.remove(k);
+        Some(v)
+    }
+
+    /// Iterates the entries whose keys fall in `range`, in key order.
+    pub fn range<R>(&self, range: R) -> impl Iterator<Item = (&K, &V)>
+    where
+        R: RangeBounds<K>,
+    {
+        self.index.range(range).map(move |k| {
+            let v = self
+                .inner
+                .get(k)
+                .expect("every indexed key is in the table");
+            (k, v)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SortedIndexHashTable;
+
+    #[test]
+    fn test_range() {
+        let mut hash_table = SortedIndexHashTable::new();
+        for k in (0..100).rev() {
+            hash_table.insert(k, k * 10);
+        }
+        assert_eq!(hash_table.insert(42, 0), Some(420));
+        assert_eq!(hash_table.remove(&43), Some(430));
+        assert_eq!(hash_table.remove(&43), None);
+
+        let found: Vec<_> = hash_table.range(40..45).collect();
+        assert_eq!(
+            found,
+            vec![(&40, &400), (&41, &410), (&42, &0), (&44, &440)]
+        );
+        assert_eq!(hash_table.range(95..).count(), 5);
+        assert_eq!(
+            hash_table.range(..=2).map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(hash_table.get(&99), Some(&990));
+    }
+}
@@ -0,0 +1,139 @@
+use std::future::{self, Future};
+use std::hash::Hash;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::{DefaultSimpleHasher, HashTable, SimpleHasher, MAX_PREALLOCATED_ENTRIES};
+
+// how many entries a load inserts before giving the other tasks on its executor a turn
+const YIELD_EVERY: usize = 1024;
+
+impl<K, V> HashTable<K, V, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    /// Builds a table from the pairs `stream` yields. See `extend_from_stream`.
+    pub async fn from_stream<S>(stream: S) -> HashTable<K, V, DefaultSimpleHasher>
+    where
+        S: Stream<Item = (K, V)>,
+    {
+        let mut hash_table = HashTable::new();
+        hash_table.extend_from_stream(stream).await;
+        hash_table
+    }
+}
+
+impl<K, V, H> HashTable<K, V, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    /// Inserts every pair `stream` yields, growing once up front for the lower bound of its size hint,
+    /// up to a few thousand entries since the hint could claim anything.
+    /// A repeated key keeps its last value. Every so many entries the load yields to the executor, so a
+    /// stream that is always ready can't starve the tasks sharing its thread.
+    pub async fn extend_from_stream<S>(&mut self, stream: S)
+    where
+        S: Stream<Item = (K, V)>,
+    {
+        let mut stream = pin!(stream);
+        self.reserve_exact(stream.size_hint().0.min(MAX_PREALLOCATED_ENTRIES));
+
+        let mut since_yield = 0;
+        while let Some((k, v)) = future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            self.insert(k, v);
+            since_yield += 1;
+            if since_yield == YIELD_EVERY {
+                since_yield = 0;
+                YieldNow { yielded: false }.await;
+            }
+        }
+    }
+}
+
+// returns pending once, asking to be polled again straight away
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+    use std::task::{Context, Poll};
+
+    use futures::executor::block_on;
+    use futures::stream;
+    use futures::task::noop_waker_ref;
+    use futures::Future;
+
+    use crate::HashTable;
+
+    #[test]
+    fn test_from_stream() {
+        let pairs = stream::iter((0..3000).map(|k| (k, k * 10)));
+        let mut hash_table = block_on(HashTable::from_stream(pairs));
+        for k in 0..3000 {
+            assert_eq!(hash_table.get(&k), Some(&(k * 10)));
+        }
+
+        block_on(hash_table.extend_from_stream(stream::iter(vec![(0, 1), (3000, 2)])));
+        assert_eq!(hash_table.get(&0), Some(&1));
+        assert_eq!(hash_table.get(&3000), Some(&2));
+    }
+
+    #[test]
+    fn test_extend_from_stream_yields_to_the_executor() {
+        let mut hash_table = HashTable::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        {
+            let mut load =
+                pin!(hash_table.extend_from_stream(stream::iter((0..1500).map(|k| (k, k)))));
+
+            // the stream is always ready, but the load stops after its first batch anyway
+            assert_eq!(load.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(load.as_mut().poll(&mut cx), Poll::Ready(()));
+        }
+        assert_eq!(hash_table.get(&1499), Some(&1499));
+    }
+
+    #[test]
+    fn test_extend_from_stream_caps_the_size_hint() {
+        // claims far more entries than it has, or than could ever be allocated
+        struct Boastful(std::vec::IntoIter<(u64, u64)>);
+        impl futures::Stream for Boastful {
+            type Item = (u64, u64);
+
+            fn poll_next(
+                mut self: std::pin::Pin<&mut Self>,
+                _: &mut Context<'_>,
+            ) -> Poll<Option<(u64, u64)>> {
+                Poll::Ready(self.0.next())
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (usize::MAX, None)
+            }
+        }
+
+        let hash_table = block_on(HashTable::from_stream(Boastful(
+            vec![(0, 0), (1, 10)].into_iter(),
+        )));
+        assert_eq!(hash_table.get(&1), Some(&10));
+        assert!(hash_table.capacity() < 10_000);
+    }
+}
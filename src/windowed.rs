@@ -0,0 +1,196 @@
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::{DefaultSimpleHasher, HashTable, SimpleHasher};
+
+// one key's counts, a ring of buckets indexed by tick modulo its length
+struct Window {
+    counts: Vec<u64>,
+    // the newest tick written to, everything in the ring is at most a full ring older than it
+    latest: u64,
+}
+
+impl Window {
+    // moves the ring forward to `tick`, clearing buckets that fell out of the window on the way
+    fn rotate(&mut self, tick: u64) {
+        let number_of_buckets = self.counts.len() as u64;
+        if tick <= self.latest {
+            return;
+        }
+        if tick - self.latest >= number_of_buckets {
+            self.counts.iter_mut().for_each(|c| *c = 0);
+        } else {
+            for stale in self.latest + 1..=tick {
+                self.counts[(stale % number_of_buckets) as usize] = 0;
+            }
+        }
+        self.latest = tick;
+    }
+
+    fn record(&mut self, tick: u64) {
+        self.rotate(tick);
+        let number_of_buckets = self.counts.len() as u64;
+        self.counts[(tick % number_of_buckets) as usize] += 1;
+    }
+
+    // the sum of the `buckets` newest buckets as of `tick`
+    fn count(&self, buckets: u64, tick: u64) -> u64 {
+        let number_of_buckets = self.counts.len() as u64;
+        let oldest_live = (self.latest + 1).saturating_sub(number_of_buckets);
+        let from = (tick + 1).saturating_sub(buckets).max(oldest_live);
+        (from..=tick.min(self.latest))
+            .map(|t| self.counts[(t % number_of_buckets) as usize])
+            .sum()
+    }
+}
+
+/// Counts events per key over a sliding window of time, such as requests per key in the last minute.
+///
+/// The window is split into a ring of equally sized time buckets per key. Buckets that fall out of the
+/// window are cleared as the ring rotates, so counts are only as precise as a single bucket.
+pub struct WindowedCounterTable<K, H = DefaultSimpleHasher>
+where
+    H: SimpleHasher<K>,
+    K: Hash,
+{
+    inner: HashTable<K, Window, H>,
+    epoch: Instant,
+    bucket_width: u128,
+    number_of_buckets: usize,
+}
+
+impl<K> WindowedCounterTable<K, DefaultSimpleHasher>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new(
+        window: Duration,
+        number_of_buckets: usize,
+    ) -> WindowedCounterTable<K, DefaultSimpleHasher> {
+        WindowedCounterTable::with_hasher(window, number_of_buckets, DefaultSimpleHasher::new())
+    }
+}
+
+impl<K, H> WindowedCounterTable<K, H>
+where
+    K: Hash + PartialEq,
+    H: SimpleHasher<K>,
+{
+    /// Tracks `window` worth of events split into `number_of_buckets` buckets. Panics if there are no
+    /// buckets or they would be shorter than a nanosecond.
+    pub fn with_hasher(
+        window: Duration,
+        number_of_buckets: usize,
+        hasher: H,
+    ) -> WindowedCounterTable<K, H> {
+        assert!(number_of_buckets > 0, "a window needs at least one bucket");
+        let bucket_width = window.as_nanos() / number_of_buckets as u128;
+        assert!(
+            bucket_width > 0,
+            "window buckets must be at least a nanosecond long"
+        );
+
+        WindowedCounterTable {
+            inner: HashTable::with_hasher(hasher),
+            epoch: Instant::now(),
+            bucket_width,
+            number_of_buckets,
+        }
+    }
+
+    /// Records one event for `k` now.
+    pub fn record(&mut self, k: &K)
+    where
+        K: Clone,
+    {
+        let tick = self.tick();
+        self.record_at(k, tick);
+    }
+
+    /// How many events were recorded for `k` in the last `duration`, rounded up to whole buckets and
+    /// capped at the table's window.
+    pub fn count_last(&self, k: &K, duration: Duration) -> u64 {
+        let buckets = duration.as_nanos().div_ceil(self.bucket_width) as u64;
+        self.count_at(k, buckets, self.tick())
+    }
+
+    /// Removes every key without events left in the window, returning how many were removed.
+    pub fn remove_expired(&mut self) -> usize {
+        let tick = self.tick();
+        self.remove_expired_at(tick)
+    }
+
+    fn remove_expired_at(&mut self, tick: u64) -> usize {
+        let number_of_buckets = self.number_of_buckets as u64;
+        self.inner
+            .retain_entries(|_, window| window.latest + number_of_buckets > tick)
+    }
+
+    fn record_at(&mut self, k: &K, tick: u64)
+    where
+        K: Clone,
+    {
+        // only a key seen for the first time is cloned
+        match self.inner.get_mut(k) {
+            Some(window) => window.record(tick),
+            None => {
+                let mut window = Window {
+                    counts: vec![0; self.number_of_buckets],
+                    latest: tick,
+                };
+                window.record(tick);
+                self.inner.insert(k.clone(), window);
+            }
+        }
+    }
+
+    fn count_at(&self, k: &K, buckets: u64, tick: u64) -> u64 {
+        self.inner
+            .get(k)
+            .map_or(0, |window| window.count(buckets, tick))
+    }
+
+    fn tick(&self) -> u64 {
+        (self.epoch.elapsed().as_nanos() / self.bucket_width) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::WindowedCounterTable;
+
+    #[test]
+    fn test_sliding_window() {
+        // ten one second buckets, driven by hand through ticks
+        let mut counters = WindowedCounterTable::new(Duration::from_secs(10), 10);
+        for tick in 0..5 {
+            counters.record_at(&"gedalia", tick);
+            counters.record_at(&"gedalia", tick);
+        }
+        counters.record_at(&"theo", 2);
+
+        assert_eq!(counters.count_at(&"gedalia", 10, 4), 10);
+        assert_eq!(counters.count_at(&"gedalia", 2, 4), 4);
+        assert_eq!(counters.count_at(&"gedalia", 2, 6), 0);
+        assert_eq!(counters.count_at(&"theo", 10, 11), 1);
+        assert_eq!(counters.count_at(&"theo", 10, 12), 0);
+        assert_eq!(counters.count_at(&"nowhereman", 10, 4), 0);
+
+        // buckets that slid out of the window are cleared before being reused
+        counters.record_at(&"gedalia", 12);
+        assert_eq!(counters.count_at(&"gedalia", 10, 12), 5);
+        counters.record_at(&"gedalia", 40);
+        assert_eq!(counters.count_at(&"gedalia", 10, 40), 1);
+
+        assert_eq!(counters.remove_expired_at(41), 1);
+        assert_eq!(counters.count_at(&"gedalia", 10, 41), 1);
+
+        // the public entry points read the clock, which hasn't reached the second bucket yet
+        counters.record(&"aviva");
+        counters.record(&"aviva");
+        assert_eq!(counters.count_last(&"aviva", Duration::from_secs(1)), 2);
+        assert_eq!(counters.remove_expired(), 0);
+    }
+}